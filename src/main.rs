@@ -1,5 +1,8 @@
-use crate::importer::Importer;
-use crate::objects::config::Config;
+use crate::i18n::Lang;
+use crate::logging::LogFormat;
+use crate::objects::config::{Config, Secret};
+use crate::objects::excel_mapper::{excel_column_to_index, FieldSource};
+use crate::report::RunReport;
 use anyhow::Result;
 use std::path::PathBuf;
 use structopt::StructOpt;
@@ -7,55 +10,3474 @@ use structopt::StructOpt;
 extern crate anyhow;
 #[macro_use]
 extern crate serde_json;
+#[macro_use]
+extern crate tracing;
+
+/// Looks `msg` up in the message catalogue of the current language, filling its `{}`
+/// placeholders with the arguments
+macro_rules! tr {
+    ($msg:ident) => {
+        $crate::i18n::text($crate::i18n::Msg::$msg)
+    };
+    ($msg:ident, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::fill($crate::i18n::text($crate::i18n::Msg::$msg), &[$(&$arg),+])
+    };
+}
+
+/// Prints a line meant for people, on stderr when stdout carries the JSON summary
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if $crate::report::human_output_is_stderr() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
 
 #[derive(StructOpt, Debug)]
-#[structopt(name = "Zendesk Ticket Importer")]
+#[structopt(
+    name = "Zendesk Ticket Importer",
+    after_help = tr!(AfterHelp),
+    help_message = tr!(HelpMessage),
+    version_message = tr!(VersionMessage)
+)]
 pub struct Opt {
-    /// Activate debug mode
-    #[structopt(short, long)]
+    #[structopt(short, long, global = true, help = tr!(HelpDebug))]
     debug: bool,
 
-    /// Sets verbosity level (-v, -vv, -vvv)
-    #[structopt(short, long, parse(from_occurrences))]
+    #[structopt(short, long, parse(from_occurrences), global = true, help = tr!(HelpVerbose))]
     verbose: u8,
 
-    /// Input .xls, .xlsx, xlsb, or ods file
-    #[structopt(name = "FILE", parse(from_os_str))]
-    file: PathBuf,
+    #[structopt(
+        long,
+        default_value = "text",
+        possible_values = &["text", "json"],
+        global = true,
+        help = tr!(HelpLogFormat)
+    )]
+    log_format: LogFormat,
+
+    /// Already applied by `i18n::init` before parsing, so `--help` is translated too
+    #[structopt(long, possible_values = &["en", "pt-BR"], global = true, help = tr!(HelpLang))]
+    lang: Option<Lang>,
+
+    #[structopt(name = "FILE", parse(from_os_str), help = tr!(HelpFiles))]
+    files: Vec<PathBuf>,
+
+    #[structopt(short, long, name = "CONFIG_FILE", global = true, help = tr!(HelpConfig))]
+    config_path: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        env = "ZENDESK_API_TOKEN",
+        hide_env_values = true,
+        global = true,
+        parse(from_str),
+        help = tr!(HelpApiToken)
+    )]
+    api_token: Option<Secret>,
+
+    #[structopt(long, env = "ZENDESK_EMAIL", global = true, help = tr!(HelpEmail))]
+    email: Option<String>,
+
+    #[structopt(long, env = "ZENDESK_SUBDOMAIN", global = true, help = tr!(HelpSubdomain))]
+    subdomain: Option<String>,
+
+    #[structopt(long, help = tr!(HelpAllowPartial))]
+    allow_partial: bool,
+
+    #[structopt(long, help = tr!(HelpUpsert))]
+    upsert: bool,
+
+    #[structopt(long, parse(from_os_str), global = true, help = tr!(HelpSummary))]
+    summary: Option<PathBuf>,
+
+    #[structopt(subcommand)]
+    cmd: Option<Command>,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    #[structopt(
+        about = tr!(CmdInit),
+        help_message = tr!(HelpMessage),
+        version_message = tr!(VersionMessage)
+    )]
+    Init(InitOpt),
+    #[structopt(
+        about = tr!(CmdFields),
+        help_message = tr!(HelpMessage),
+        version_message = tr!(VersionMessage)
+    )]
+    Fields(FieldsOpt),
+    #[structopt(
+        about = tr!(CmdUpdate),
+        help_message = tr!(HelpMessage),
+        version_message = tr!(VersionMessage)
+    )]
+    Update(UpdateOpt),
+    #[structopt(
+        about = tr!(CmdExport),
+        help_message = tr!(HelpMessage),
+        version_message = tr!(VersionMessage)
+    )]
+    Export(ExportOpt),
+}
+
+#[derive(StructOpt, Debug)]
+pub struct InitOpt {
+    #[structopt(name = "WORKBOOK", parse(from_os_str), help = tr!(HelpInitWorkbook))]
+    workbook: PathBuf,
+
+    #[structopt(long, help = tr!(HelpInitSheet))]
+    sheet: Option<String>,
+
+    #[structopt(long, default_value = "1", help = tr!(HelpInitHeaderRow))]
+    header_row: usize,
+
+    #[structopt(short, long, parse(from_os_str), help = tr!(HelpInitOutput))]
+    output: Option<PathBuf>,
+
+    #[structopt(short, long, help = tr!(HelpInitYes))]
+    yes: bool,
+
+    #[structopt(long, help = tr!(HelpInitForce))]
+    force: bool,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct FieldsOpt {
+    #[structopt(long, help = tr!(HelpFieldsJson))]
+    json: bool,
+
+    #[structopt(short, long, help = tr!(HelpFieldsAll))]
+    all: bool,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct UpdateOpt {
+    #[structopt(name = "FILE", parse(from_os_str), required = true, help = tr!(HelpFiles))]
+    files: Vec<PathBuf>,
+
+    #[structopt(long, help = tr!(HelpUpdateIdColumn))]
+    id_column: Option<String>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ExportOpt {
+    #[structopt(long, required_unless = "view", help = tr!(HelpExportQuery))]
+    query: Option<String>,
+
+    #[structopt(long, conflicts_with = "query", help = tr!(HelpExportView))]
+    view: Option<u64>,
+
+    #[structopt(short, long, parse(from_os_str), help = tr!(HelpExportOutput))]
+    output: PathBuf,
+}
+
+/// Exit status when the run finished but some rows didn't make it into Zendesk. Errors
+/// that stop the run exit with 1.
+const EXIT_PARTIAL_FAILURE: i32 = 2;
+
+fn exit_on_failures(report: &RunReport) -> Result<()> {
+    if report.has_failures() {
+        std::process::exit(EXIT_PARTIAL_FAILURE);
+    }
+    Ok(())
+}
+
+// TODO: Create installation script for linux and windows
+#[tokio::main]
+async fn main() -> Result<()> {
+    i18n::init(&std::env::args_os().collect::<Vec<_>>());
+    let mut opt = Opt::from_args();
+    if let Some(lang) = opt.lang {
+        i18n::set(lang);
+    }
+    logging::init(&opt);
+    match opt.cmd.take() {
+        Some(Command::Init(init_opt)) => init::run(&opt, &init_opt).await,
+        Some(Command::Fields(fields_opt)) => fields::run(&mut opt, &fields_opt).await,
+        Some(Command::Update(update_opt)) => {
+            let mut config = Config::from_opt(&mut opt)?;
+            if let Some(column) = &update_opt.id_column {
+                let id = excel_column_to_index(column)
+                    .map(FieldSource::column)
+                    .ok_or_else(|| anyhow!(tr!(NotAColumnLetter, column)))?;
+                config.ticket.system_fields.id = Some(id.clone());
+                for overrides in config.sheets.values_mut() {
+                    if let Some(ticket) = overrides.ticket.as_mut() {
+                        ticket.system_fields.id = Some(id.clone());
+                    }
+                }
+            }
+            let report = importer::update(&update_opt.files, config).await?;
+            exit_on_failures(&report)
+        }
+        Some(Command::Export(export_opt)) => {
+            let config = Config::from_opt(&mut opt)?;
+            export::run(&config, &export_opt).await
+        }
+        None => {
+            if opt.files.is_empty() {
+                return Err(anyhow!(tr!(MissingFile)));
+            }
+            let files = opt.files.clone();
+            let config = Config::from_opt(&mut opt)?;
+            let report = importer::run(&files, config).await?;
+            exit_on_failures(&report)
+        }
+    }
+}
+
+pub mod i18n {
+    use std::env;
+    use std::ffi::OsString;
+    use std::fmt::{self, Write};
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicU8, Ordering};
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Lang {
+        En,
+        PtBr,
+    }
+
+    impl FromStr for Lang {
+        type Err = anyhow::Error;
+
+        /// Takes "en" and "pt-BR" as well as locales such as "pt_BR.UTF-8"
+        fn from_str(name: &str) -> anyhow::Result<Self> {
+            let name = name.to_ascii_lowercase();
+            if name.starts_with("pt") {
+                Ok(Lang::PtBr)
+            } else if name.starts_with("en") || name == "c" || name == "posix" {
+                Ok(Lang::En)
+            } else {
+                Err(anyhow!(tr!(UnknownLang, name)))
+            }
+        }
+    }
+
+    static LANG: AtomicU8 = AtomicU8::new(Lang::En as u8);
+
+    /// Picks the language from `--lang`, or else from the LC_ALL, LC_MESSAGES or LANG
+    /// locale. It runs before the command line is parsed so `--help` is translated too.
+    pub fn init(args: &[OsString]) {
+        let from_args = args.iter().enumerate().find_map(|(idx, arg)| {
+            let arg = arg.to_str()?;
+            match arg.strip_prefix("--lang") {
+                Some("") => args.get(idx + 1)?.to_str()?.parse().ok(),
+                Some(value) => value.strip_prefix('=')?.parse().ok(),
+                None => None,
+            }
+        });
+        let from_locale = || {
+            ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .filter_map(|name| env::var(name).ok())
+                .find(|value| !value.is_empty())
+                .and_then(|value| value.parse().ok())
+        };
+        set(from_args.or_else(from_locale).unwrap_or(Lang::En));
+    }
+
+    pub fn set(lang: Lang) {
+        LANG.store(lang as u8, Ordering::Relaxed);
+    }
+
+    pub fn lang() -> Lang {
+        match LANG.load(Ordering::Relaxed) {
+            x if x == Lang::PtBr as u8 => Lang::PtBr,
+            _ => Lang::En,
+        }
+    }
+
+    /// Every user facing text. `{}` placeholders are filled in order by `tr!`.
+    #[derive(Debug, Clone, Copy)]
+    pub enum Msg {
+        // command line help
+        AfterHelp,
+        HelpMessage,
+        VersionMessage,
+        HelpDebug,
+        HelpVerbose,
+        HelpLogFormat,
+        HelpFiles,
+        HelpConfig,
+        HelpApiToken,
+        HelpEmail,
+        HelpSubdomain,
+        HelpAllowPartial,
+        HelpUpsert,
+        HelpSummary,
+        HelpLang,
+        CmdInit,
+        CmdFields,
+        CmdUpdate,
+        CmdExport,
+        HelpInitWorkbook,
+        HelpInitSheet,
+        HelpInitHeaderRow,
+        HelpInitOutput,
+        HelpInitYes,
+        HelpInitForce,
+        HelpFieldsJson,
+        HelpFieldsAll,
+        HelpUpdateIdColumn,
+        HelpExportQuery,
+        HelpExportView,
+        HelpExportOutput,
+        CsvNotImportable,
+        MissingFile,
+        // cells that could not be converted
+        CellEmpty,
+        CellNotText,
+        CellNotANumber,
+        CellNotAnInteger,
+        CellOutOfRange,
+        CellNotABoolean,
+        CellNotADate,
+        CellUnknownOption,
+        CellUnknownName,
+        CellNotATicketId,
+        CellNotAnAssignee,
+        CellUnknownTimezone,
+        CellUnsupportedFieldType,
+        ExpectedText,
+        ExpectedInteger,
+        ExpectedDecimal,
+        ExpectedDate,
+        ExpectedCheckbox,
+        ExpectedOption,
+        ExpectedPriority,
+        ExpectedStatus,
+        ExpectedTicketType,
+        ExpectedTicketId,
+        ExpectedAssignee,
+        LiteralCell,
+        // errors that stop the run
+        ConfigUnreadable,
+        ConfigInvalid,
+        NoApiToken,
+        SecretEnvUnset,
+        SecretFileUnreadable,
+        SecretKeyringUnreadable,
+        NoKeyringSupport,
+        NoEmail,
+        NoSubdomain,
+        BadCredentialChars,
+        BadSheetPattern,
+        NoSheetName,
+        NoSheetsFound,
+        NoSuchSheet,
+        NoIdColumn,
+        ConfigMismatch,
+        LiteralInvalid,
+        SourceRead,
+        ApiAnswered,
+        PayloadTooLarge,
+        TicketEncoding,
+        JobFailed,
+        HttpSetup,
+        HttpBuild,
+        HttpNoResponse,
+        HttpUnreadable,
+        UnknownLang,
+        UnknownLogFormat,
+        NotAColumnLetter,
+        DirectoryUnreadable,
+        BadFilePattern,
+        NoWorkbooksFound,
+        FileUnreadable,
+        SummaryUnwritable,
+        UnknownDedupKey,
+        BadFilter,
+        FilterNeedsColumn,
+        BadFilterRegex,
+        FilterNotADate,
+        WorksheetUnreadable,
+        UnexpectedAnswer,
+        UpsertNeedsExternalId,
+        LiteralNoDate,
+        ColumnAndValue,
+        ValueWithDefault,
+        ColumnOrValueRequired,
+        ColumnRequired,
+        ColumnNotLetters,
+        // config validation
+        FieldInactive,
+        FieldTypeUnsupported,
+        ColumnMappedTwice,
+        ColumnBeyondSheet,
+        FieldRequired,
+        UnknownFieldId,
+        UnknownField,
+        UnknownFieldSuggestions,
+        Or,
+        // run summary
+        SheetHeader,
+        TotalPrefix,
+        Counts,
+        LineInvalid,
+        LineInvalidCells,
+        LineDuplicate,
+        DuplicateSkipped,
+        DuplicateImported,
+        RepeatsLine,
+        ExternalIdRepeats,
+        MatchesTicket,
+        LineRejected,
+        RejectedByZendesk,
+        MissingFromJob,
+        ProgressBar,
+        ProgressLine,
+        ProgressCounts,
+        ProgressBarCounts,
+        EtaUnknown,
+        // init
+        OverwriteConfig,
+        ConfigExists,
+        ConfirmYes,
+        ConfirmNo,
+        HeaderRowZero,
+        WorkbookUnopenable,
+        NoWorksheets,
+        WorksheetNotFound,
+        HeaderRowEmpty,
+        AskSubdomain,
+        AskEmail,
+        AskApiToken,
+        TokenUnreadable,
+        MappingHelp,
+        MappingProposal,
+        MappingNoMatch,
+        MappingUnknownField,
+        MappingTaken,
+        NoCommentColumn,
+        AskCommentColumn,
+        AskTimezone,
+        DirectoryUncreatable,
+        ConfigUnwritable,
+        ConfigWritten,
+        TokenFromEnv,
+        EndOfInput,
+        // fields
+        FieldsId,
+        FieldsTitle,
+        FieldsType,
+        FieldsActive,
+        FieldsRequired,
+        Yes,
+        No,
+        // export
+        NoExportSource,
+        BadExportExtension,
+        OutputUnwritable,
+        Exported,
+    }
+
+    /// The text for `msg` in the current language
+    pub fn text(msg: Msg) -> &'static str {
+        use Msg::*;
+        match lang() {
+            Lang::En => match msg {
+                AfterHelp => "Exits with 0 when every row went through, 2 when some rows were invalid or rejected by Zendesk and 1 when the run could not finish.",
+                HelpMessage => "Prints help information",
+                VersionMessage => "Prints version information",
+                HelpDebug => "Activate debug mode, logs every request sent to Zendesk",
+                HelpVerbose => "Sets verbosity level (-v, -vv, -vvv), -vvv logs how every row was converted",
+                HelpLogFormat => "Log as plain text or as one JSON object per line",
+                HelpFiles => "Input .xls, .xlsx, xlsb, or ods files, directories holding them or patterns like \"lotes/*.xlsx\"",
+                HelpConfig => "Toml file. Default location on linux is $HOME/.config/zendesk_ticket_importer",
+                HelpApiToken => "Zendesk API token, overrides credentials.api_token from the config file",
+                HelpEmail => "Zendesk agent email, overrides credentials.email from the config file",
+                HelpSubdomain => "Zendesk subdomain, overrides credentials.subdomain from the config file",
+                HelpAllowPartial => "Import even if the config doesn't fully match the account's ticket fields",
+                HelpUpsert => "Update tickets whose external_id already exists instead of creating them again",
+                HelpSummary => "Write a JSON summary of the run to this file, - for stdout, overrides import.summary",
+                HelpLang => "Language of the messages, en or pt-BR. Defaults to the system locale",
+                CmdInit => "Generates a config file from a sample workbook and the account's ticket fields",
+                CmdFields => "Lists the account's ticket fields, their types and dropdown options",
+                CmdUpdate => "Updates existing tickets, one column holds the id of the ticket to change",
+                CmdExport => "Writes existing tickets to a workbook laid out like the config, ready to be edited and imported again",
+                HelpInitWorkbook => "Sample .xls, .xlsx, xlsb, or ods file whose header row will be mapped",
+                HelpInitSheet => "Worksheet holding the tickets, defaults to the first one in the workbook",
+                HelpInitHeaderRow => "Row number of the header row, tickets are expected right below it",
+                HelpInitOutput => "Where to write the config. Defaults to CONFIG_FILE or its default location",
+                HelpInitYes => "Accept every proposed mapping without asking",
+                HelpInitForce => "Overwrite an existing config without asking",
+                HelpFieldsJson => "Print the fields as JSON instead of a table",
+                HelpFieldsAll => "Also list inactive fields",
+                HelpUpdateIdColumn => "Column holding the ticket ids, overrides ticket.system_fields.id",
+                HelpExportQuery => "Search query selecting the tickets, e.g. \"status:open created>2020-01-01\"",
+                HelpExportView => "Id of the view whose tickets are exported",
+                HelpExportOutput => "Output .xlsx file, or .csv for other tools (save it as .xlsx to import it again)",
+                CsvNotImportable => "CSV files can't be imported, save it as .xlsx before importing it again",
+                MissingFile => "Missing input FILE, see --help",
+                CellEmpty => "the cell is empty",
+                CellNotText => "\"{}\" is not text",
+                CellNotANumber => "\"{}\" is not a number",
+                CellNotAnInteger => "\"{}\" is not a whole number",
+                CellOutOfRange => "{} is outside the range Zendesk accepts, {} to {}",
+                CellNotABoolean => "\"{}\" is not a yes or no value",
+                CellNotADate => "\"{}\" is not a date",
+                CellUnknownOption => "\"{}\" is not one of the options: {}",
+                CellUnknownName => "\"{}\" is not valid, expected {}",
+                CellNotATicketId => "\"{}\" is not a ticket id",
+                CellNotAnAssignee => "\"{}\" is neither a user id nor an email",
+                CellUnknownTimezone => "Unknown timezone \"{}\", expected Acre, DeNoronha, East or West",
+                CellUnsupportedFieldType => "Unknown Zendesk field type \"{}\"",
+                ExpectedText => "text",
+                ExpectedInteger => "integer",
+                ExpectedDecimal => "decimal number",
+                ExpectedDate => "date",
+                ExpectedCheckbox => "checkbox",
+                ExpectedOption => "dropdown option",
+                ExpectedPriority => "priority",
+                ExpectedStatus => "status",
+                ExpectedTicketType => "ticket type",
+                ExpectedTicketId => "ticket id",
+                ExpectedAssignee => "user id or email",
+                LiteralCell => "value",
+                ConfigUnreadable => "Could not read config file \"{}\": {}",
+                ConfigInvalid => "Config file \"{}\" is not valid: {}",
+                NoApiToken => "No API token found, set credentials.api_token or pass --api-token",
+                SecretEnvUnset => "Environment variable {} is not set, it holds credentials.api_token",
+                SecretFileUnreadable => "Could not read secret file {}: {}",
+                SecretKeyringUnreadable => "Could not read {} for {} from the OS keyring: {}",
+                NoKeyringSupport => "This build has no OS keyring support, rebuild with --features keyring",
+                NoEmail => "No email found, set credentials.email or pass --email",
+                NoSubdomain => "No subdomain found, set credentials.subdomain or pass --subdomain",
+                BadCredentialChars => "The email or API token has characters that can't be sent",
+                BadSheetPattern => "worksheet.name_matches is not a valid regular expression: {}",
+                NoSheetName => "Set worksheet.name or worksheet.name_matches",
+                NoSheetsFound => "None of the workbooks has the worksheets to import",
+                NoSuchSheet => "there is no worksheet \"{}\"",
+                NoIdColumn => "Set ticket.system_fields.id or pass --id-column to update tickets",
+                ConfigMismatch => "The config doesn't match the account's ticket fields, fix the issues above or pass --allow-partial",
+                LiteralInvalid => "A value set in the config doesn't fit its field: {}",
+                SourceRead => "Could not read \"{}\": {}",
+                ApiAnswered => "Zendesk answered {}: {}",
+                PayloadTooLarge => "The request is too large",
+                TicketEncoding => "Could not encode the ticket as JSON: {}",
+                JobFailed => "Job {} {}: {}",
+                HttpSetup => "Could not set up the HTTP client",
+                HttpBuild => "Could not build the request",
+                HttpNoResponse => "Zendesk server didn't respond",
+                HttpUnreadable => "Could not read Zendesk's answer",
+                UnknownLang => "Unknown language \"{}\", expected en or pt-BR",
+                UnknownLogFormat => "Unknown log format, expected text or json",
+                NotAColumnLetter => "\"{}\" is not a column letter",
+                DirectoryUnreadable => "Could not read directory \"{}\"",
+                BadFilePattern => "Invalid pattern \"{}\"",
+                NoWorkbooksFound => "No workbooks found in {}",
+                FileUnreadable => "Could not read file \"{}\"",
+                SummaryUnwritable => "Could not write summary to \"{}\"",
+                UnknownDedupKey => "Unknown dedup key \"{}\"",
+                BadFilter => "Invalid worksheet filter #{}",
+                FilterNeedsColumn => "A column like \"E\" is required",
+                BadFilterRegex => "Bad regex \"{}\"",
+                FilterNotADate => "Expected a number or a date, got \"{}\"",
+                WorksheetUnreadable => "worksheet \"{}\": {}",
+                UnexpectedAnswer => "Unexpected answer ({}): {}",
+                UpsertNeedsExternalId => "Upsert mode needs ticket.system_fields.external_id to be mapped",
+                LiteralNoDate => "{} has no date",
+                ColumnAndValue => "use either column or value, not both",
+                ValueWithDefault => "a value can't have a default",
+                ColumnOrValueRequired => "either a column or a value is required",
+                ColumnRequired => "a column is required",
+                ColumnNotLetters => "Excel columns are one to three letters",
+                FieldInactive => "Field {} is inactive in Zendesk, its values would be ignored",
+                FieldTypeUnsupported => "Field {} has type \"{}\", which can't be imported",
+                ColumnMappedTwice => "Column {} is mapped to more than one field: {}",
+                ColumnBeyondSheet => "Column {} ({}) is beyond the last column of the sheet, {}",
+                FieldRequired => "Field \"{}\" is required in Zendesk but isn't mapped to any column",
+                UnknownFieldId => "Unknown field id {} in [ticket.custom_fields]",
+                UnknownField => "Unknown field \"{}\" in [ticket.custom_fields]",
+                UnknownFieldSuggestions => "Unknown field \"{}\" in [ticket.custom_fields], did you mean {}?",
+                Or => " or ",
+                SheetHeader => "Sheet \"{}\" of \"{}\"",
+                TotalPrefix => "Total: ",
+                Counts => "{}{} created, {} updated, {} unchanged, {} duplicates, {} skipped, {} invalid, {} rejected",
+                LineInvalid => "Line {} is invalid: {}",
+                LineInvalidCells => "Line {} of \"{}\" is invalid:",
+                LineDuplicate => "Line {} {}, {}",
+                DuplicateSkipped => "skipped",
+                DuplicateImported => "imported anyway",
+                RepeatsLine => "repeats line {}",
+                ExternalIdRepeats => "external_id \"{}\" repeats line {}",
+                MatchesTicket => "matches ticket #{}",
+                LineRejected => "Line {} was rejected by Zendesk: {}",
+                RejectedByZendesk => "Rejected by Zendesk",
+                MissingFromJob => "Missing from job {} results",
+                ProgressBar => "{bar:20} {pos}/{len} rows, {per_sec}, ETA {eta}, batches {msg}",
+                ProgressLine => "{}/{} rows, {} rows/s, ETA {}, {}",
+                ProgressCounts => "{} batches sent, {} jobs done, {} failed",
+                ProgressBarCounts => "{} sent/{} done, {} failed",
+                EtaUnknown => "unknown",
+                OverwriteConfig => "\"{}\" already exists, overwrite it?",
+                ConfigExists => "\"{}\" already exists, pass --force to overwrite it",
+                ConfirmYes => "[Y/n]",
+                ConfirmNo => "[y/N]",
+                HeaderRowZero => "Rows are numbered starting from 1",
+                WorkbookUnopenable => "Could not open \"{}\"",
+                NoWorksheets => "\"{}\" has no worksheets",
+                WorksheetNotFound => "Could not find worksheet \"{}\"",
+                HeaderRowEmpty => "Row {} of \"{}\" is empty, use --header-row to point at the header row",
+                AskSubdomain => "Zendesk subdomain: ",
+                AskEmail => "Agent email: ",
+                AskApiToken => "API token: ",
+                TokenUnreadable => "Could not read the API token",
+                MappingHelp => "Press enter to accept a proposal, \"n\" to skip the column or type a field name",
+                MappingProposal => "{} \"{}\" -> {} ({}%) [Y/n/field]: ",
+                MappingNoMatch => "{} \"{}\" -> (no match) [skip/field]: ",
+                MappingUnknownField => "Unknown field \"{}\"",
+                MappingTaken => "{} is already mapped to column {}",
+                NoCommentColumn => "Could not find a comment column, rerun without --yes to pick one",
+                AskCommentColumn => "Which column holds the ticket comment? ",
+                AskTimezone => "Timezone, one of Acre, DeNoronha, East, West [East]: ",
+                DirectoryUncreatable => "Could not create directory \"{}\"",
+                ConfigUnwritable => "Could not write config file \"{}\"",
+                ConfigWritten => "Config written to \"{}\"",
+                TokenFromEnv => "The API token is read from $ZENDESK_API_TOKEN, edit credentials.api_token to change that",
+                EndOfInput => "Reached end of input while waiting for an answer",
+                FieldsId => "ID",
+                FieldsTitle => "TITLE",
+                FieldsType => "TYPE",
+                FieldsActive => "ACTIVE",
+                FieldsRequired => "REQUIRED",
+                Yes => "yes",
+                No => "no",
+                NoExportSource => "Pass either --query or --view",
+                BadExportExtension => "The output file must end in .xlsx or .csv",
+                OutputUnwritable => "Could not write \"{}\"",
+                Exported => "Exported {} tickets to \"{}\"",
+            },
+            Lang::PtBr => match msg {
+                AfterHelp => "Termina com 0 quando todas as linhas foram importadas, 2 quando alguma linha era inválida ou foi recusada pelo Zendesk e 1 quando a execução não pôde terminar.",
+                HelpMessage => "Mostra esta ajuda",
+                VersionMessage => "Mostra a versão",
+                HelpDebug => "Ativa o modo de depuração, registra cada requisição enviada ao Zendesk",
+                HelpVerbose => "Nível de detalhe dos logs (-v, -vv, -vvv), -vvv registra a conversão de cada linha",
+                HelpLogFormat => "Logs em texto simples ou um objeto JSON por linha",
+                HelpFiles => "Arquivos .xls, .xlsx, xlsb ou ods, pastas com esses arquivos ou padrões como \"lotes/*.xlsx\"",
+                HelpConfig => "Arquivo toml. No linux o padrão é $HOME/.config/zendesk_ticket_importer",
+                HelpApiToken => "Token da API do Zendesk, substitui credentials.api_token do arquivo de configuração",
+                HelpEmail => "Email do agente no Zendesk, substitui credentials.email do arquivo de configuração",
+                HelpSubdomain => "Subdomínio do Zendesk, substitui credentials.subdomain do arquivo de configuração",
+                HelpAllowPartial => "Importa mesmo que a configuração não corresponda totalmente aos campos da conta",
+                HelpUpsert => "Atualiza os tickets cujo external_id já existe em vez de criá-los de novo",
+                HelpSummary => "Grava um resumo da execução em JSON neste arquivo, - para a saída padrão, substitui import.summary",
+                HelpLang => "Idioma das mensagens, en ou pt-BR. O padrão é o idioma do sistema",
+                CmdInit => "Gera um arquivo de configuração a partir de uma planilha de exemplo e dos campos de ticket da conta",
+                CmdFields => "Lista os campos de ticket da conta, seus tipos e opções de lista suspensa",
+                CmdUpdate => "Atualiza tickets existentes, uma coluna contém o id do ticket a alterar",
+                CmdExport => "Grava tickets existentes numa planilha no formato da configuração, pronta para ser editada e importada de novo",
+                HelpInitWorkbook => "Arquivo .xls, .xlsx, xlsb ou ods de exemplo cujo cabeçalho será mapeado",
+                HelpInitSheet => "Aba com os tickets, o padrão é a primeira da planilha",
+                HelpInitHeaderRow => "Número da linha do cabeçalho, os tickets começam logo abaixo",
+                HelpInitOutput => "Onde gravar a configuração. O padrão é CONFIG_FILE ou o local padrão",
+                HelpInitYes => "Aceita todos os mapeamentos propostos sem perguntar",
+                HelpInitForce => "Sobrescreve uma configuração existente sem perguntar",
+                HelpFieldsJson => "Mostra os campos em JSON em vez de uma tabela",
+                HelpFieldsAll => "Lista também os campos inativos",
+                HelpUpdateIdColumn => "Coluna com os ids dos tickets, substitui ticket.system_fields.id",
+                HelpExportQuery => "Busca que seleciona os tickets, por exemplo \"status:open created>2020-01-01\"",
+                HelpExportView => "Id da visualização cujos tickets serão exportados",
+                HelpExportOutput => "Arquivo .xlsx de saída, ou .csv para outras ferramentas (salve-o como .xlsx para importá-lo de novo)",
+                CsvNotImportable => "Arquivos CSV não podem ser importados, salve-o como .xlsx antes de importá-lo de novo",
+                MissingFile => "Falta o arquivo de entrada FILE, veja --help",
+                CellEmpty => "a célula está vazia",
+                CellNotText => "\"{}\" não é texto",
+                CellNotANumber => "\"{}\" não é um número",
+                CellNotAnInteger => "\"{}\" não é um número inteiro",
+                CellOutOfRange => "{} está fora do intervalo aceito pelo Zendesk, de {} a {}",
+                CellNotABoolean => "\"{}\" não é um valor de sim ou não",
+                CellNotADate => "\"{}\" não é uma data",
+                CellUnknownOption => "\"{}\" não é uma das opções: {}",
+                CellUnknownName => "\"{}\" não é válido, esperado {}",
+                CellNotATicketId => "\"{}\" não é um id de ticket",
+                CellNotAnAssignee => "\"{}\" não é um id de usuário nem um email",
+                CellUnknownTimezone => "Fuso horário \"{}\" desconhecido, esperado Acre, DeNoronha, East ou West",
+                CellUnsupportedFieldType => "Tipo de campo do Zendesk \"{}\" desconhecido",
+                ExpectedText => "texto",
+                ExpectedInteger => "número inteiro",
+                ExpectedDecimal => "número decimal",
+                ExpectedDate => "data",
+                ExpectedCheckbox => "caixa de seleção",
+                ExpectedOption => "opção da lista suspensa",
+                ExpectedPriority => "prioridade",
+                ExpectedStatus => "status",
+                ExpectedTicketType => "tipo de ticket",
+                ExpectedTicketId => "id de ticket",
+                ExpectedAssignee => "id de usuário ou email",
+                LiteralCell => "valor",
+                ConfigUnreadable => "Não foi possível ler o arquivo de configuração \"{}\": {}",
+                ConfigInvalid => "O arquivo de configuração \"{}\" não é válido: {}",
+                NoApiToken => "Nenhum token da API encontrado, defina credentials.api_token ou use --api-token",
+                SecretEnvUnset => "A variável de ambiente {} não está definida, ela guarda credentials.api_token",
+                SecretFileUnreadable => "Não foi possível ler o arquivo de segredo {}: {}",
+                SecretKeyringUnreadable => "Não foi possível ler {} de {} no chaveiro do sistema: {}",
+                NoKeyringSupport => "Esta versão não tem suporte ao chaveiro do sistema, compile com --features keyring",
+                NoEmail => "Nenhum email encontrado, defina credentials.email ou use --email",
+                NoSubdomain => "Nenhum subdomínio encontrado, defina credentials.subdomain ou use --subdomain",
+                BadCredentialChars => "O email ou o token da API têm caracteres que não podem ser enviados",
+                BadSheetPattern => "worksheet.name_matches não é uma expressão regular válida: {}",
+                NoSheetName => "Defina worksheet.name ou worksheet.name_matches",
+                NoSheetsFound => "Nenhuma das planilhas tem as abas a importar",
+                NoSuchSheet => "não existe a aba \"{}\"",
+                NoIdColumn => "Defina ticket.system_fields.id ou use --id-column para atualizar tickets",
+                ConfigMismatch => "A configuração não corresponde aos campos de ticket da conta, corrija os problemas acima ou use --allow-partial",
+                LiteralInvalid => "Um valor definido na configuração não serve para o seu campo: {}",
+                SourceRead => "Não foi possível ler \"{}\": {}",
+                ApiAnswered => "O Zendesk respondeu {}: {}",
+                PayloadTooLarge => "A requisição é grande demais",
+                TicketEncoding => "Não foi possível codificar o ticket em JSON: {}",
+                JobFailed => "Job {} {}: {}",
+                HttpSetup => "Não foi possível configurar o cliente HTTP",
+                HttpBuild => "Não foi possível montar a requisição",
+                HttpNoResponse => "O servidor do Zendesk não respondeu",
+                HttpUnreadable => "Não foi possível ler a resposta do Zendesk",
+                UnknownLang => "Idioma \"{}\" desconhecido, use en ou pt-BR",
+                UnknownLogFormat => "Formato de log desconhecido, use text ou json",
+                NotAColumnLetter => "\"{}\" não é uma letra de coluna",
+                DirectoryUnreadable => "Não foi possível ler a pasta \"{}\"",
+                BadFilePattern => "Padrão \"{}\" inválido",
+                NoWorkbooksFound => "Nenhuma planilha encontrada em {}",
+                FileUnreadable => "Não foi possível ler o arquivo \"{}\"",
+                SummaryUnwritable => "Não foi possível gravar o resumo em \"{}\"",
+                UnknownDedupKey => "Chave de deduplicação \"{}\" desconhecida",
+                BadFilter => "O filtro de aba #{} é inválido",
+                FilterNeedsColumn => "Falta uma coluna como \"E\"",
+                BadFilterRegex => "Expressão regular \"{}\" inválida",
+                FilterNotADate => "Esperava um número ou uma data, veio \"{}\"",
+                WorksheetUnreadable => "aba \"{}\": {}",
+                UnexpectedAnswer => "Resposta inesperada ({}): {}",
+                UpsertNeedsExternalId => "O modo upsert precisa que ticket.system_fields.external_id esteja mapeado",
+                LiteralNoDate => "{} não tem data",
+                ColumnAndValue => "use column ou value, não os dois",
+                ValueWithDefault => "um value não pode ter default",
+                ColumnOrValueRequired => "é preciso uma column ou um value",
+                ColumnRequired => "é preciso uma column",
+                ColumnNotLetters => "as colunas do Excel têm de uma a três letras",
+                FieldInactive => "O campo {} está inativo no Zendesk, seus valores seriam ignorados",
+                FieldTypeUnsupported => "O campo {} é do tipo \"{}\", que não pode ser importado",
+                ColumnMappedTwice => "A coluna {} está mapeada para mais de um campo: {}",
+                ColumnBeyondSheet => "A coluna {} ({}) fica depois da última coluna da aba, {}",
+                FieldRequired => "O campo \"{}\" é obrigatório no Zendesk mas não está mapeado para nenhuma coluna",
+                UnknownFieldId => "Id de campo {} desconhecido em [ticket.custom_fields]",
+                UnknownField => "Campo \"{}\" desconhecido em [ticket.custom_fields]",
+                UnknownFieldSuggestions => "Campo \"{}\" desconhecido em [ticket.custom_fields], você quis dizer {}?",
+                Or => " ou ",
+                SheetHeader => "Aba \"{}\" de \"{}\"",
+                TotalPrefix => "Total: ",
+                Counts => "{}{} criados, {} atualizados, {} sem mudanças, {} duplicados, {} ignorados, {} inválidos, {} recusados",
+                LineInvalid => "A linha {} é inválida: {}",
+                LineInvalidCells => "A linha {} de \"{}\" é inválida:",
+                LineDuplicate => "A linha {} {}, {}",
+                DuplicateSkipped => "ignorada",
+                DuplicateImported => "importada mesmo assim",
+                RepeatsLine => "repete a linha {}",
+                ExternalIdRepeats => "o external_id \"{}\" repete a linha {}",
+                MatchesTicket => "corresponde ao ticket #{}",
+                LineRejected => "A linha {} foi recusada pelo Zendesk: {}",
+                RejectedByZendesk => "Recusada pelo Zendesk",
+                MissingFromJob => "Ausente dos resultados do job {}",
+                ProgressBar => "{bar:20} {pos}/{len} linhas, {per_sec}, faltam {eta}, lotes {msg}",
+                ProgressLine => "{}/{} linhas, {} linhas/s, faltam {}, {}",
+                ProgressCounts => "{} lotes enviados, {} jobs concluídos, {} com falha",
+                ProgressBarCounts => "{} enviados/{} concluídos, {} com falha",
+                EtaUnknown => "desconhecido",
+                OverwriteConfig => "\"{}\" já existe, sobrescrever?",
+                ConfigExists => "\"{}\" já existe, use --force para sobrescrevê-lo",
+                ConfirmYes => "[S/n]",
+                ConfirmNo => "[s/N]",
+                HeaderRowZero => "As linhas são numeradas a partir de 1",
+                WorkbookUnopenable => "Não foi possível abrir \"{}\"",
+                NoWorksheets => "\"{}\" não tem abas",
+                WorksheetNotFound => "A aba \"{}\" não foi encontrada",
+                HeaderRowEmpty => "A linha {} de \"{}\" está vazia, use --header-row para indicar a linha de cabeçalho",
+                AskSubdomain => "Subdomínio do Zendesk: ",
+                AskEmail => "Email do agente: ",
+                AskApiToken => "Token da API: ",
+                TokenUnreadable => "Não foi possível ler o token da API",
+                MappingHelp => "Tecle enter para aceitar a sugestão, \"n\" para pular a coluna ou digite o nome de um campo",
+                MappingProposal => "{} \"{}\" -> {} ({}%) [S/n/campo]: ",
+                MappingNoMatch => "{} \"{}\" -> (sem correspondência) [pular/campo]: ",
+                MappingUnknownField => "Campo \"{}\" desconhecido",
+                MappingTaken => "{} já está mapeado para a coluna {}",
+                NoCommentColumn => "Nenhuma coluna de comentário encontrada, rode de novo sem --yes para escolher uma",
+                AskCommentColumn => "Qual coluna tem o comentário do ticket? ",
+                AskTimezone => "Fuso horário, um de Acre, DeNoronha, East, West [East]: ",
+                DirectoryUncreatable => "Não foi possível criar a pasta \"{}\"",
+                ConfigUnwritable => "Não foi possível gravar o arquivo de configuração \"{}\"",
+                ConfigWritten => "Configuração gravada em \"{}\"",
+                TokenFromEnv => "O token da API é lido de $ZENDESK_API_TOKEN, edite credentials.api_token para mudar isso",
+                EndOfInput => "A entrada terminou antes da resposta",
+                FieldsId => "ID",
+                FieldsTitle => "TÍTULO",
+                FieldsType => "TIPO",
+                FieldsActive => "ATIVO",
+                FieldsRequired => "OBRIGATÓRIO",
+                Yes => "sim",
+                No => "não",
+                NoExportSource => "Use --query ou --view",
+                BadExportExtension => "O arquivo de saída deve terminar em .xlsx ou .csv",
+                OutputUnwritable => "Não foi possível gravar \"{}\"",
+                Exported => "{} tickets exportados para \"{}\"",
+            },
+        }
+    }
+
+    /// Fills the `{}` placeholders of `template` in order
+    pub fn fill(template: &str, args: &[&dyn fmt::Display]) -> String {
+        let mut parts = template.split("{}");
+        let mut filled = parts.next().unwrap_or_default().to_string();
+        for (part, arg) in parts.zip(args.iter()) {
+            let _ = write!(filled, "{}", arg);
+            filled.push_str(part);
+        }
+        filled
+    }
+}
+
+pub mod logging {
+    use crate::Opt;
+    use anyhow::Result;
+    use std::io::{self, IsTerminal};
+    use std::str::FromStr;
+    use tracing::Level;
+    use tracing_subscriber::filter::Targets;
+    use tracing_subscriber::prelude::*;
+    use tracing_subscriber::{fmt, registry};
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum LogFormat {
+        Text,
+        Json,
+    }
+
+    impl FromStr for LogFormat {
+        type Err = anyhow::Error;
+
+        fn from_str(name: &str) -> Result<Self> {
+            match name {
+                "text" => Ok(LogFormat::Text),
+                "json" => Ok(LogFormat::Json),
+                _ => Err(anyhow!(tr!(UnknownLogFormat))),
+            }
+        }
+    }
+
+    /// Logs go to stderr, warnings only unless asked for more: -v for info, -vv or
+    /// --debug for requests and responses, -vvv for every row's conversion
+    pub fn init(opt: &Opt) {
+        let level = match opt.verbose {
+            0 if opt.debug => Level::DEBUG,
+            0 => Level::WARN,
+            1 if opt.debug => Level::DEBUG,
+            1 => Level::INFO,
+            2 => Level::DEBUG,
+            _ => Level::TRACE,
+        };
+        // libraries stay at warnings, their trace output is mostly connection pooling
+        let targets = Targets::new()
+            .with_target(env!("CARGO_CRATE_NAME"), level)
+            .with_default(Level::WARN);
+        let layer = fmt::layer()
+            .with_writer(io::stderr)
+            .with_ansi(io::stderr().is_terminal())
+            .with_target(false);
+        match opt.log_format {
+            LogFormat::Text => registry().with(layer.with_filter(targets)).init(),
+            LogFormat::Json => registry().with(layer.json().with_filter(targets)).init(),
+        }
+    }
+}
+
+pub mod progress {
+    use crate::report;
+    use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+    use std::io::{self, IsTerminal};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    /// How often a progress line is printed when the output isn't a terminal
+    const PLAIN_INTERVAL: Duration = Duration::from_secs(10);
+
+    /// How far a sheet got, drawn as a bar on terminals and printed as a plain line
+    /// every `PLAIN_INTERVAL` otherwise, e.g. when running from cron.
+    pub struct Progress {
+        bar: Option<ProgressBar>,
+        total: u64,
+        rows: AtomicU64,
+        batches: AtomicU64,
+        jobs: AtomicU64,
+        failed: AtomicU64,
+        started: Instant,
+        last_line: Mutex<Instant>,
+    }
+
+    impl Progress {
+        /// `total` is how many rows the sheet may hold, blank and filtered rows included
+        pub fn new(total: u64) -> Self {
+            let to_stderr = report::human_output_is_stderr();
+            let is_terminal = if to_stderr {
+                io::stderr().is_terminal()
+            } else {
+                io::stdout().is_terminal()
+            };
+            let bar = if is_terminal {
+                let target = if to_stderr {
+                    ProgressDrawTarget::stderr()
+                } else {
+                    ProgressDrawTarget::stdout()
+                };
+                let bar = ProgressBar::with_draw_target(Some(total), target);
+                bar.set_style(
+                    ProgressStyle::with_template(tr!(ProgressBar))
+                        .expect("valid progress template"),
+                );
+                Some(bar)
+            } else {
+                None
+            };
+            let started = Instant::now();
+            Progress {
+                bar,
+                total,
+                rows: AtomicU64::new(0),
+                batches: AtomicU64::new(0),
+                jobs: AtomicU64::new(0),
+                failed: AtomicU64::new(0),
+                started,
+                last_line: Mutex::new(started),
+            }
+        }
+
+        pub fn row_read(&self) {
+            self.rows.fetch_add(1, Ordering::Relaxed);
+            if let Some(bar) = &self.bar {
+                bar.inc(1);
+            }
+            self.refresh();
+        }
+
+        pub fn batch_sent(&self) {
+            self.batches.fetch_add(1, Ordering::Relaxed);
+            self.refresh();
+        }
+
+        pub fn job_done(&self) {
+            self.jobs.fetch_add(1, Ordering::Relaxed);
+            self.refresh();
+        }
+
+        /// Rows that could not be converted or that Zendesk rejected
+        pub fn failed(&self, rows: usize) {
+            self.failed.fetch_add(rows as u64, Ordering::Relaxed);
+            self.refresh();
+        }
+
+        /// Rows read from the sheet so far, blank ones aside
+        pub fn rows_read(&self) -> u64 {
+            self.rows.load(Ordering::Relaxed)
+        }
+
+        pub fn finish(&self) {
+            if let Some(bar) = &self.bar {
+                bar.finish_and_clear();
+            }
+        }
+
+        fn counts(&self) -> String {
+            tr!(
+                ProgressCounts,
+                self.batches.load(Ordering::Relaxed),
+                self.jobs.load(Ordering::Relaxed),
+                self.failed.load(Ordering::Relaxed)
+            )
+        }
+
+        fn refresh(&self) {
+            if let Some(bar) = &self.bar {
+                bar.set_message(tr!(
+                    ProgressBarCounts,
+                    self.batches.load(Ordering::Relaxed),
+                    self.jobs.load(Ordering::Relaxed),
+                    self.failed.load(Ordering::Relaxed)
+                ));
+                return;
+            }
+            let mut last_line = self.last_line.lock().unwrap();
+            if last_line.elapsed() < PLAIN_INTERVAL {
+                return;
+            }
+            *last_line = Instant::now();
+            let rows = self.rows.load(Ordering::Relaxed);
+            let rate = rows as f64 / self.started.elapsed().as_secs_f64().max(1.0);
+            let eta = if rate > 0.0 {
+                format!("{:.0}s", self.total.saturating_sub(rows) as f64 / rate)
+            } else {
+                tr!(EtaUnknown).to_string()
+            };
+            say!(
+                "{}",
+                tr!(
+                    ProgressLine,
+                    rows,
+                    self.total,
+                    format!("{:.1}", rate),
+                    eta,
+                    self.counts()
+                )
+            );
+        }
+    }
+}
+
+pub mod importer {
+    use crate::api::{JobStatus, TicketField, ZendeskClient};
+    use crate::error::{CellError, ImportError};
+    use crate::filter::RowFilters;
+    use crate::objects::config::{Config, ImportMode, OnDuplicate};
+    use crate::objects::ticket::{is_blank, Ticket, TicketWrapper};
+    use crate::progress::Progress;
+    use crate::report::{self, RunReport, RunSummary, SheetReport};
+    use crate::{dedup, upsert, validation};
+    use anyhow::{Context, Error, Result};
+    use calamine::{self, open_workbook_auto, DataType, Range, Reader, Sheets};
+    use chrono::Utc;
+    use futures::stream::{self, StreamExt};
+    use regex::Regex;
+    use serde_json::Value;
+    use std::borrow::Cow;
+    use std::cell::Cell;
+    use std::collections::{HashMap, HashSet};
+    use std::fs;
+    use std::future::Future;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    /// Most tickets the bulk endpoints take per request
+    const MAX_BATCH_SIZE: usize = 100;
+
+    /// Rows converted together, `invalid` holds the ones that could not be converted,
+    /// with their cells in `cell_errors`, and `skipped` the ones the worksheet filters
+    /// left out
+    struct Batch<T> {
+        items: Vec<(usize, T)>,
+        invalid: Vec<(usize, String)>,
+        cell_errors: Vec<CellError>,
+        skipped: Vec<usize>,
+    }
+
+    impl<T> Batch<T> {
+        fn new(capacity: usize) -> Self {
+            Batch {
+                items: Vec::with_capacity(capacity),
+                invalid: Vec::new(),
+                cell_errors: Vec::new(),
+                skipped: Vec::new(),
+            }
+        }
+
+        /// Adds the rows that weren't sent to the report
+        fn record_left_out(self, report: &mut RunReport) {
+            report.invalid_rows.extend(self.invalid);
+            report.cell_errors.extend(self.cell_errors);
+            report.skipped.extend(self.skipped);
+        }
+    }
+
+    impl<T: Clone> Batch<T> {
+        fn of(items: &[(usize, T)]) -> Self {
+            let mut batch = Batch::new(items.len());
+            batch.items.extend_from_slice(items);
+            batch
+        }
+    }
+
+    /// Rows whose cells are all empty, often just formatting left over below the data
+    fn is_blank_row(row: &[DataType]) -> bool {
+        row.iter().all(|cell| is_blank(Some(cell)))
+    }
+
+    const WORKBOOK_EXTENSIONS: [&str; 5] = ["xls", "xlsx", "xlsm", "xlsb", "ods"];
+
+    /// Imports every selected sheet of every workbook in `inputs`, one after the other,
+    /// sharing the client so they all stay within the same request budget.
+    /// Returns the totals of every sheet.
+    pub async fn run(inputs: &[PathBuf], config: Config) -> Result<RunReport> {
+        each_sheet(inputs, config, false).await
+    }
+
+    /// Same as `run`, but rows update the tickets whose id is in the sheet
+    pub async fn update(inputs: &[PathBuf], config: Config) -> Result<RunReport> {
+        each_sheet(inputs, config, true).await
+    }
+
+    /// Imports every selected sheet. The summary is written even when the run stops
+    /// on an error, holding the sheets done so far and that error.
+    async fn each_sheet(inputs: &[PathBuf], config: Config, update: bool) -> Result<RunReport> {
+        let started = Utc::now();
+        let started_at = Instant::now();
+        if config.import.summary.as_deref() == Some(Path::new("-")) {
+            report::human_output_to_stderr();
+        }
+        let mut reports = Vec::new();
+        let mut api_calls = 0;
+        let outcome = match ZendeskClient::new(&config.credentials, &config.http) {
+            Ok(client) => {
+                let client = Arc::new(client.with_rate_limit(config.import.requests_per_minute));
+                let outcome = import_sheets(inputs, &config, update, &client, &mut reports).await;
+                api_calls = client.api_calls();
+                outcome
+            }
+            Err(err) => Err(err.into()),
+        };
+        let total = RunReport::total(reports.iter().map(|sheet| &sheet.report));
+        if outcome.is_ok() && reports.len() > 1 {
+            total.print_counts(tr!(TotalPrefix));
+        }
+        if let Some(path) = &config.import.summary {
+            let mode = match (update, config.import.mode) {
+                (true, _) => "update",
+                (false, ImportMode::Create) => "create",
+                (false, ImportMode::Upsert) => "upsert",
+            };
+            let summary = RunSummary {
+                mode,
+                started,
+                duration: started_at.elapsed(),
+                api_calls,
+                sheets: &reports,
+                total: &total,
+                error: outcome.as_ref().err().map(|err| format!("{:#}", err)),
+            };
+            summary.write(path)?;
+        }
+        outcome.map(|()| total)
+    }
+
+    async fn import_sheets(
+        inputs: &[PathBuf],
+        config: &Config,
+        update: bool,
+        client: &Arc<ZendeskClient>,
+        reports: &mut Vec<SheetReport>,
+    ) -> Result<()> {
+        let sheets = select_sheets(&input_files(inputs)?, config)?;
+        let api_fields = client.get_api_fields(&config.urls.get_fields).await?;
+        // every sheet is checked before the first row is sent, so a mismatch on the last
+        // one doesn't stop the run with the others already imported. Sheets are read
+        // again to import them rather than all kept in memory meanwhile.
+        for (file, sheet) in sheets.iter() {
+            let range = read_sheet(file, sheet)?;
+            validate_sheet(&config.for_sheet(sheet), &api_fields, &range, !update)?;
+        }
+        let several = sheets.len() > 1;
+        for (file, sheet) in sheets {
+            if several {
+                say!("{}", tr!(SheetHeader, sheet, file.display()));
+            }
+            let range = read_sheet(&file, &sheet)?;
+            let importer = Importer::new(range, config.for_sheet(&sheet), client.clone())?;
+            let (report, error) = if update {
+                importer.update(&api_fields).await
+            } else {
+                importer.run(&api_fields).await
+            };
+            importer.progress.finish();
+            report.print();
+            // kept even when the run stops here, the summary lists what was done
+            reports.push(SheetReport {
+                file,
+                sheet,
+                report,
+            });
+            if let Some(err) = error {
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// The whole sheet is loaded, calamine 0.16 has no way to stream cells
+    fn read_sheet(file: &Path, sheet: &str) -> Result<Range<DataType>, ImportError> {
+        let source_read = |message| ImportError::SourceRead {
+            path: file.to_path_buf(),
+            message,
+        };
+        match open_workbook(file)?.worksheet_range(sheet) {
+            Some(Ok(range)) => Ok(range),
+            Some(Err(err)) => Err(source_read(tr!(WorksheetUnreadable, sheet, err))),
+            None => Err(source_read(tr!(NoSuchSheet, sheet))),
+        }
+    }
+
+    /// Checks the mapping against the account's fields and the sheet's columns, warning
+    /// about every issue. Issues stop the run unless `import.allow_partial` is set.
+    fn validate_sheet(
+        config: &Config,
+        api_fields: &[TicketField],
+        range: &Range<DataType>,
+        creates_tickets: bool,
+    ) -> Result<(), ImportError> {
+        if !creates_tickets && config.ticket.system_fields.id.is_none() {
+            return Err(ImportError::Config(tr!(NoIdColumn).to_string()));
+        }
+        let last_column = range.end().map(|(_, col)| col as usize).unwrap_or(0);
+        let issues = validation::validate(config, api_fields, last_column, creates_tickets)?;
+        let sheet = config.worksheet.name.first().map_or("", String::as_str);
+        for issue in issues.iter() {
+            warn!(sheet, "{}", issue);
+        }
+        if !issues.is_empty() && !config.import.allow_partial {
+            return Err(ImportError::Config(tr!(ConfigMismatch).to_string()));
+        }
+        Ok(())
+    }
+
+    /// Workbooks named on the command line, directories stand for the workbooks inside
+    /// them and patterns like `lotes/*.xlsx` are expanded.
+    fn input_files(inputs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for input in inputs {
+            let text = input.to_string_lossy();
+            if input.is_dir() {
+                let mut found: Vec<PathBuf> = fs::read_dir(input)
+                    .with_context(|| tr!(DirectoryUnreadable, input.display()))?
+                    .filter_map(|entry| Some(entry.ok()?.path()))
+                    .filter(|path| is_workbook(path))
+                    .collect();
+                found.sort();
+                files.extend(found);
+            } else if text.contains(['*', '?', '[']) {
+                let mut found: Vec<PathBuf> = glob::glob(&text)
+                    .with_context(|| tr!(BadFilePattern, text))?
+                    .filter_map(|path| path.ok())
+                    .filter(|path| path.is_file() && is_workbook(path))
+                    .collect();
+                found.sort();
+                files.extend(found);
+            } else {
+                files.push(input.clone());
+            }
+        }
+        if files.is_empty() {
+            return Err(anyhow!(tr!(NoWorkbooksFound, format!("{:?}", inputs))));
+        }
+        Ok(files)
+    }
+
+    /// Leaves out other files and the lock files Excel keeps next to open workbooks
+    fn is_workbook(path: &Path) -> bool {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        extension.is_some_and(|e| WORKBOOK_EXTENSIONS.contains(&e.as_str()))
+            && !name.starts_with("~$")
+    }
+
+    fn open_workbook(file: &Path) -> Result<Sheets, ImportError> {
+        open_workbook_auto(file).map_err(|err| ImportError::SourceRead {
+            path: file.to_path_buf(),
+            message: err.to_string(),
+        })
+    }
+
+    /// Every (file, sheet) pair picked by `worksheet.name` and `worksheet.name_matches`
+    fn select_sheets(
+        files: &[PathBuf],
+        config: &Config,
+    ) -> Result<Vec<(PathBuf, String)>, ImportError> {
+        let worksheet = &config.worksheet;
+        let pattern = worksheet
+            .name_matches
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|err| ImportError::Config(tr!(BadSheetPattern, err)))?;
+        if worksheet.name.is_empty() && pattern.is_none() {
+            return Err(ImportError::Config(tr!(NoSheetName).to_string()));
+        }
+        let mut sheets = Vec::new();
+        for file in files {
+            let workbook = open_workbook(file)?;
+            let names = workbook.sheet_names();
+            for name in worksheet.name.iter().filter(|name| !names.contains(name)) {
+                warn!(file = ?file, "{}", tr!(NoSuchSheet, name));
+            }
+            let selected = names.iter().filter(|name| {
+                worksheet.name.contains(name) || pattern.as_ref().is_some_and(|p| p.is_match(name))
+            });
+            sheets.extend(selected.map(|name| (file.clone(), name.clone())));
+        }
+        if sheets.is_empty() {
+            return Err(ImportError::Config(tr!(NoSheetsFound).to_string()));
+        }
+        Ok(sheets)
+    }
+
+    /// calamine rows start at the first used column, but the mapping counts columns
+    /// from A. A sheet whose first columns are blank, like an exported one, would
+    /// otherwise be read shifted to the left. Only the row being read is padded, the
+    /// sheet itself isn't copied again.
+    fn from_column_a(row: &[DataType], first_col: usize) -> Cow<'_, [DataType]> {
+        if first_col == 0 {
+            return Cow::Borrowed(row);
+        }
+        let mut padded = vec![DataType::Empty; first_col];
+        padded.extend_from_slice(row);
+        Cow::Owned(padded)
+    }
+
+    pub struct Importer {
+        range: Range<DataType>,
+        config: Config,
+        client: Arc<ZendeskClient>,
+        filters: RowFilters,
+        progress: Progress,
+    }
+
+    impl Importer {
+        pub fn new(
+            range: Range<DataType>,
+            config: Config,
+            client: Arc<ZendeskClient>,
+        ) -> Result<Self> {
+            let filters = RowFilters::new(&config.worksheet.filters)?;
+            let last_row = range.end().map_or(0, |(row, _)| row as usize + 1);
+            let last_row = last_row.min(config.worksheet.bottom_row.unwrap_or(usize::MAX));
+            let progress =
+                Progress::new((last_row + 1).saturating_sub(config.worksheet.top_row) as u64);
+            Ok(Importer {
+                range,
+                config,
+                client,
+                filters,
+                progress,
+            })
+        }
+
+        /// Rows from `top_row` to `bottom_row`, paired with their 1-based line number in
+        /// the sheet. The range only starts at the first non empty row and column, which
+        /// aren't always row 1 and column A. Blank rows are left out, or end the sheet
+        /// with `stop_at_first_blank`.
+        fn ticket_rows(&self) -> impl Iterator<Item = (usize, Cow<'_, [DataType]>)> {
+            let worksheet = &self.config.worksheet;
+            let (first_row, first_col) = self
+                .range
+                .start()
+                .map_or((0, 0), |(row, col)| (row as usize, col as usize));
+            let top_row = worksheet.top_row.max(1);
+            let bottom_row = worksheet.bottom_row.unwrap_or(usize::MAX);
+            let stop_at_first_blank = worksheet.stop_at_first_blank;
+            self.range
+                .rows()
+                .enumerate()
+                .map(move |(idx, row)| (idx + first_row + 1, from_column_a(row, first_col)))
+                .skip_while(move |(row, _)| *row < top_row)
+                .take_while(move |(row, cells)| {
+                    *row <= bottom_row && !(stop_at_first_blank && is_blank_row(cells))
+                })
+                .filter(|(_, cells)| !is_blank_row(cells))
+        }
+
+        fn batch_size(&self) -> usize {
+            self.config.import.batch_size.clamp(1, MAX_BATCH_SIZE)
+        }
+
+        /// Converts rows lazily, `batch_size` valid rows at a time, so only the batches
+        /// currently being submitted are held as tickets. The cells themselves are not
+        /// streamed: calamine 0.16 reads the whole sheet into `range` first, so memory
+        /// still grows with the size of the sheet.
+        fn batches<'a, T, F>(&'a self, convert: F) -> impl Iterator<Item = Batch<T>> + 'a
+        where
+            F: Fn(usize, &[DataType]) -> Result<T, ImportError> + 'a,
+        {
+            let batch_size = self.batch_size();
+            let mut rows = self.ticket_rows().peekable();
+            std::iter::from_fn(move || {
+                rows.peek()?;
+                let mut batch = Batch::new(batch_size);
+                while batch.items.len() < batch_size {
+                    let (row_num, row) = match rows.next() {
+                        Some(row) => row,
+                        None => break,
+                    };
+                    self.progress.row_read();
+                    if !self.filters.matches(row_num, &row) {
+                        trace!(line = row_num, "Skipped by the worksheet filters");
+                        batch.skipped.push(row_num);
+                        continue;
+                    }
+                    let _line = trace_span!("row", line = row_num).entered();
+                    match convert(row_num, &row) {
+                        Ok(item) => batch.items.push((row_num, item)),
+                        Err(err) => {
+                            debug!(line = row_num, error = %err, "Invalid row");
+                            self.progress.failed(1);
+                            batch.invalid.push((row_num, err.to_string()));
+                            if let ImportError::InvalidRow(errors) = err {
+                                batch.cell_errors.extend(errors.0);
+                            }
+                        }
+                    }
+                }
+                Some(batch)
+            })
+        }
+
+        /// Every valid row at once, for the modes that need to look at the whole sheet
+        /// before sending anything. Dedup and upsert runs hold every converted ticket on
+        /// top of the sheet's cells.
+        fn collect_tickets(
+            &self,
+            api_fields: &[TicketField],
+            report: &mut RunReport,
+        ) -> Vec<(usize, Ticket)> {
+            let mut tickets = Vec::new();
+            let batches =
+                self.batches(|line, row| Ticket::from_row(row, line, &self.config, api_fields));
+            for mut batch in batches {
+                tickets.append(&mut batch.items);
+                batch.record_left_out(report);
+            }
+            tickets
+        }
+
+        /// The sheet's config is expected to have gone through `validate_sheet`. The
+        /// report is returned even when an error stopped the run, holding the rows
+        /// that were done by then.
+        pub async fn run(&self, api_fields: &[TicketField]) -> (RunReport, Option<Error>) {
+            let mut report = RunReport::default();
+            let outcome = match self.config.import.mode {
+                ImportMode::Create if self.config.dedup.is_none() => {
+                    let batches = self
+                        .batches(|line, row| Ticket::from_row(row, line, &self.config, api_fields));
+                    self.stream_tickets(batches, Self::create_batch, false, &mut report)
+                        .await
+                }
+                ImportMode::Create => {
+                    let tickets = self.collect_tickets(api_fields, &mut report);
+                    self.create_tickets(tickets, api_fields, &mut report).await
+                }
+                ImportMode::Upsert => {
+                    let tickets = self.collect_tickets(api_fields, &mut report);
+                    self.upsert_tickets(tickets, api_fields, &mut report).await
+                }
+            };
+            report.rows = self.progress.rows_read();
+            (report, outcome.err())
+        }
+
+        async fn create_tickets(
+            &self,
+            mut tickets: Vec<(usize, Ticket)>,
+            api_fields: &[TicketField],
+            report: &mut RunReport,
+        ) -> Result<()> {
+            if let Some(settings) = &self.config.dedup {
+                let duplicates = dedup::find_duplicates(
+                    &self.client,
+                    &self.config.urls,
+                    settings,
+                    api_fields,
+                    &tickets,
+                )
+                .await?;
+                if settings.on_duplicate == OnDuplicate::Skip {
+                    let rows: HashSet<usize> = duplicates.iter().map(|(row, _)| *row).collect();
+                    tickets.retain(|(row, _)| !rows.contains(row));
+                }
+                report.duplicates_skipped = settings.on_duplicate == OnDuplicate::Skip;
+                report.duplicates.extend(duplicates);
+            }
+            let batches = tickets.chunks(self.batch_size()).map(Batch::of);
+            self.stream_tickets(batches, Self::create_batch, false, report)
+                .await
+        }
+
+        async fn create_batch(&self, tickets: Vec<Ticket>) -> Result<JobStatus, ImportError> {
+            let wrapper = TicketWrapper { tickets };
+            let job = self
+                .client
+                .create_many(&self.config.urls.post_many, &wrapper)
+                .await?;
+            self.wait_for_job(job).await
+        }
+
+        async fn update_batch(&self, tickets: Vec<Value>) -> Result<JobStatus, ImportError> {
+            let job = self
+                .client
+                .update_many(&self.config.urls.update_many, &tickets)
+                .await?;
+            self.wait_for_job(job).await
+        }
+
+        /// Rows whose external_id already exists in Zendesk only send the fields that
+        /// changed to `update_many`, every other row is created as usual.
+        async fn upsert_tickets(
+            &self,
+            tickets: Vec<(usize, Ticket)>,
+            api_fields: &[TicketField],
+            report: &mut RunReport,
+        ) -> Result<()> {
+            if self.config.ticket.system_fields.external_id.is_none() {
+                return Err(anyhow!(tr!(UpsertNeedsExternalId)));
+            }
+            let external_ids: Vec<&str> = tickets
+                .iter()
+                .filter_map(|(_, ticket)| ticket.external_id())
+                .collect();
+            let existing =
+                upsert::find_existing(&self.client, &self.config.urls, &external_ids).await?;
+
+            let mut to_create = Vec::new();
+            let mut to_update: Vec<(usize, Value)> = Vec::new();
+            let mut first_rows: HashMap<String, usize> = HashMap::new();
+            for (row, ticket) in tickets {
+                // a second row would create or update the same ticket again
+                if let Some(id) = ticket.external_id() {
+                    if let Some(first) = first_rows.get(id) {
+                        let reason = tr!(ExternalIdRepeats, id, first);
+                        report.invalid_rows.push((row, reason));
+                        continue;
+                    }
+                    first_rows.insert(id.to_string(), row);
+                }
+                match ticket.external_id().and_then(|id| existing.get(id)) {
+                    None => to_create.push((row, ticket)),
+                    Some(current) => match upsert::changed_fields(&ticket, current)? {
+                        Some(changes) => to_update.push((row, changes)),
+                        None => report.unchanged.push(row),
+                    },
+                }
+            }
+
+            self.create_tickets(to_create, api_fields, report).await?;
+            let batches = to_update.chunks(self.batch_size()).map(Batch::of);
+            self.stream_tickets(batches, Self::update_batch, true, report)
+                .await
+        }
+
+        /// Keeps up to `max_in_flight` batches going at once. The next batch is only
+        /// pulled from `batches` once one of them is done, and results are still
+        /// recorded in sheet order since `buffered` yields them in submission order.
+        async fn stream_tickets<'a, T, I, S, F>(
+            &'a self,
+            batches: I,
+            submit: S,
+            is_update: bool,
+            report: &mut RunReport,
+        ) -> Result<()>
+        where
+            T: Clone,
+            I: Iterator<Item = Batch<T>>,
+            S: Fn(&'a Self, Vec<T>) -> F,
+            F: Future<Output = Result<JobStatus, ImportError>>,
+        {
+            let submit = &submit;
+            // once a request gets no answer no new batch is sent, but the ones already
+            // in flight are still waited for so their rows make it to the report
+            let stopped = Cell::new(false);
+            let jobs = batches
+                .take_while(|_| !stopped.get())
+                .map(|mut batch| async move {
+                    let items = std::mem::take(&mut batch.items);
+                    let (jobs, error) = Self::submit_splitting(self, submit, items).await;
+                    (jobs, error, batch)
+                });
+            let mut jobs = stream::iter(jobs).buffered(self.config.import.max_in_flight.max(1));
+            let mut first_error = None;
+            while let Some((jobs, error, batch)) = jobs.next().await {
+                if let Some(err) = error {
+                    stopped.set(true);
+                    first_error.get_or_insert(err);
+                }
+                batch.record_left_out(report);
+                for (rows, job) in jobs {
+                    let failed = report.failed.len();
+                    match job {
+                        Ok(job) => report.record_job(&job, &rows, is_update),
+                        Err(err) => {
+                            warn!(
+                                first_line = rows[0],
+                                last_line = rows[rows.len() - 1],
+                                error = %err,
+                                "Zendesk refused the batch"
+                            );
+                            let reason = err.to_string();
+                            report
+                                .failed
+                                .extend(rows.iter().map(|row| (*row, reason.clone())));
+                        }
+                    }
+                    self.progress.job_done();
+                    self.progress.failed(report.failed.len() - failed);
+                }
+            }
+            match first_error {
+                Some(err) => Err(err.into()),
+                None => Ok(()),
+            }
+        }
+
+        /// Sends `items` as one job, splitting it in halves for as long as Zendesk
+        /// answers that the request is too large. Jobs come back in row order, along
+        /// with the error of the requests Zendesk refused, down to a single ticket too
+        /// large to send. Only a request that got no answer at all stops the run, it
+        /// is returned next to the jobs that were done before it.
+        async fn submit_splitting<'a, T, S, F>(
+            &'a self,
+            submit: &S,
+            items: Vec<(usize, T)>,
+        ) -> (
+            Vec<(Vec<usize>, Result<JobStatus, ImportError>)>,
+            Option<ImportError>,
+        )
+        where
+            T: Clone,
+            S: Fn(&'a Self, Vec<T>) -> F,
+            F: Future<Output = Result<JobStatus, ImportError>>,
+        {
+            let mut jobs = Vec::new();
+            let mut pending = vec![items];
+            while let Some(mut items) = pending.pop() {
+                if items.is_empty() {
+                    continue;
+                }
+                let (rows, tickets): (Vec<usize>, Vec<T>) = items.iter().cloned().unzip();
+                self.progress.batch_sent();
+                match submit(self, tickets).await {
+                    Err(ImportError::PayloadTooLarge) if items.len() > 1 => {
+                        let second_half = items.split_off(items.len() / 2);
+                        warn!(
+                            first_line = rows[0],
+                            last_line = rows[rows.len() - 1],
+                            "Batch too large for one request, sending it in two"
+                        );
+                        pending.push(second_half);
+                        pending.push(items);
+                    }
+                    Err(err @ ImportError::Http { .. }) => return (jobs, Some(err)),
+                    result => jobs.push((rows, result)),
+                }
+            }
+            (jobs, None)
+        }
+
+        /// Applies every row to the ticket whose id is in `ticket.system_fields.id`,
+        /// sending only the fields that have a value in the sheet. Like `run`, the
+        /// report comes back along with the error that stopped the run, if any.
+        pub async fn update(&self, api_fields: &[TicketField]) -> (RunReport, Option<Error>) {
+            let mut report = RunReport::default();
+            let batches = self.batches(|line, row| {
+                let ticket = Ticket::update_from_row(row, line, &self.config, api_fields)?;
+                serde_json::to_value(ticket).map_err(ImportError::TicketEncoding)
+            });
+            let outcome = self
+                .stream_tickets(batches, Self::update_batch, true, &mut report)
+                .await;
+            report.rows = self.progress.rows_read();
+            (report, outcome.err())
+        }
+
+        async fn wait_for_job(&self, job: JobStatus) -> Result<JobStatus, ImportError> {
+            self.client
+                .wait_for_job(&self.config.urls.job_statuses, job)
+                .await
+        }
+    }
+}
+
+pub mod upsert {
+    use crate::api::{ZendeskClient, SEARCH_TERMS};
+    use crate::objects::config::ApiUrls;
+    use crate::objects::ticket::Ticket;
+    use anyhow::Result;
+    use serde_json::{Map, Value};
+    use std::collections::HashMap;
+
+    /// Looks up which external ids already belong to a ticket, keyed by external id.
+    pub async fn find_existing(
+        client: &ZendeskClient,
+        urls: &ApiUrls,
+        external_ids: &[&str],
+    ) -> Result<HashMap<String, Value>> {
+        let mut existing = HashMap::new();
+        for chunk in external_ids.chunks(SEARCH_TERMS) {
+            let query = chunk.iter().fold(String::from("type:ticket"), |query, id| {
+                format!("{} external_id:\"{}\"", query, id.replace('"', "\\\""))
+            });
+            for ticket in client.search(&urls.search, &query).await? {
+                let external_id = ticket
+                    .get("external_id")
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+                // search also matches on partial words, so only keep exact hits
+                if let Some(external_id) = external_id.filter(|id| chunk.contains(&id.as_str())) {
+                    existing.insert(external_id, ticket);
+                }
+            }
+        }
+        Ok(existing)
+    }
+
+    /// Builds an `update_many` payload holding only the fields of `ticket` that differ
+    /// from `current`, or `None` when there is nothing to update. The comment is only
+    /// sent when it differs from the ticket's description, as it is added as a new
+    /// comment instead of replacing the first one. An assignee given by email can't be
+    /// compared, tickets only hold the `assignee_id`, so it is left out.
+    pub fn changed_fields(ticket: &Ticket, current: &Value) -> Result<Option<Value>> {
+        let new = serde_json::to_value(ticket)?;
+        let new = new.as_object().unwrap();
+        let mut changes = Map::new();
+        for (key, value) in new.iter() {
+            match key.as_str() {
+                "external_id" | "assignee_email" => {}
+                "comment" => {
+                    if value.get("body") != current.get("description") {
+                        changes.insert(key.clone(), value.clone());
+                    }
+                }
+                "custom_fields" => {
+                    let current_values: HashMap<u64, &Value> = current
+                        .get("custom_fields")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|f| Some((f.get("id")?.as_u64()?, f.get("value")?)))
+                        .collect();
+                    let changed: Vec<Value> = value
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter(|f| {
+                            let id = f.get("id").and_then(Value::as_u64);
+                            match id {
+                                Some(id) => current_values.get(&id) != f.get("value").as_ref(),
+                                None => false,
+                            }
+                        })
+                        .cloned()
+                        .collect();
+                    if !changed.is_empty() {
+                        changes.insert(key.clone(), Value::Array(changed));
+                    }
+                }
+                _ => {
+                    if current.get(key) != Some(value) {
+                        changes.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        if changes.is_empty() {
+            return Ok(None);
+        }
+        changes.insert("id".to_string(), current["id"].clone());
+        Ok(Some(Value::Object(changes)))
+    }
+}
+
+pub mod dedup {
+    use crate::api::{TicketField, ZendeskClient, SEARCH_TERMS};
+    use crate::objects::config::{ApiUrls, DedupSettings};
+    use crate::objects::excel_mapper::FieldRef;
+    use crate::objects::ticket::Ticket;
+    use anyhow::Result;
+    use serde_json::Value;
+    use std::collections::HashMap;
+
+    /// System fields usable as keys, with the name they have in the ticket json and
+    /// in search queries. Tickets only hold the assignee's id, so rows giving the
+    /// assignee by email have no value for that key.
+    const SYSTEM_KEYS: [(&str, &str, &str); 7] = [
+        ("subject", "subject", "subject"),
+        ("comment", "description", "description"),
+        ("status", "status", "status"),
+        ("priority", "priority", "priority"),
+        ("tickettype", "type", "ticket_type"),
+        ("assignee", "assignee_id", "assignee"),
+        ("external_id", "external_id", "external_id"),
+    ];
+
+    enum Key {
+        System {
+            json_name: &'static str,
+            search_name: &'static str,
+        },
+        Custom {
+            id: usize,
+        },
+    }
+
+    impl Key {
+        fn value_in(&self, ticket: &Value) -> Option<String> {
+            let value = match self {
+                Key::System { json_name, .. } => ticket.get(json_name),
+                Key::Custom { id } => ticket
+                    .get("custom_fields")
+                    .and_then(Value::as_array)?
+                    .iter()
+                    .find(|f| f.get("id").and_then(Value::as_u64) == Some(*id as u64))
+                    .and_then(|f| f.get("value")),
+            }?;
+            let value = match value {
+                Value::Null => return None,
+                Value::String(s) => s.trim().to_string(),
+                other => other.to_string(),
+            };
+            Some(value).filter(|v| !v.is_empty())
+        }
+
+        fn search_term(&self, value: &str) -> String {
+            let value = value.replace('"', " ");
+            match self {
+                Key::System { search_name, .. } => format!("{}:\"{}\"", search_name, value),
+                Key::Custom { id } => format!("custom_field_{}:\"{}\"", id, value),
+            }
+        }
+    }
+
+    fn resolve_keys(settings: &DedupSettings, api_fields: &[TicketField]) -> Result<Vec<Key>> {
+        settings
+            .keys
+            .iter()
+            .map(|name| {
+                if let Some((_, json_name, search_name)) =
+                    SYSTEM_KEYS.iter().find(|(key, _, _)| key == name)
+                {
+                    return Ok(Key::System {
+                        json_name,
+                        search_name,
+                    });
+                }
+                let field = if name.chars().all(|c| c.is_ascii_digit()) {
+                    FieldRef::Id(name.parse()?)
+                } else {
+                    FieldRef::Title(name.clone())
+                };
+                field
+                    .find(api_fields)
+                    .map(|f| Key::Custom { id: f.id })
+                    .ok_or_else(|| anyhow!(tr!(UnknownDedupKey, name)))
+            })
+            .collect()
+    }
+
+    /// Returns the rows of `tickets` that repeat an earlier row or an existing ticket,
+    /// along with what they duplicate. Rows missing a value for any key are never
+    /// considered duplicates. Existing tickets are looked up for many rows at once,
+    /// the search matches any of the values given for the same field.
+    pub async fn find_duplicates(
+        client: &ZendeskClient,
+        urls: &ApiUrls,
+        settings: &DedupSettings,
+        api_fields: &[TicketField],
+        tickets: &[(usize, Ticket)],
+    ) -> Result<Vec<(usize, String)>> {
+        let keys = resolve_keys(settings, api_fields)?;
+        let mut seen: HashMap<Vec<String>, usize> = HashMap::new();
+        let mut duplicates = Vec::new();
+        let mut candidates: Vec<(usize, Vec<String>)> = Vec::new();
+        for (row, ticket) in tickets {
+            let ticket = serde_json::to_value(ticket)?;
+            let mut ticket = ticket.as_object().unwrap().clone();
+            // new tickets hold the description in their first comment
+            if let Some(body) = ticket.get("comment").and_then(|c| c.get("body")).cloned() {
+                ticket.insert("description".to_string(), body);
+            }
+            let ticket = Value::Object(ticket);
+            let values: Option<Vec<String>> = keys.iter().map(|k| k.value_in(&ticket)).collect();
+            let values = match values {
+                Some(values) => values,
+                None => continue,
+            };
+            if let Some(first) = seen.get(&values) {
+                duplicates.push((*row, tr!(RepeatsLine, first)));
+                continue;
+            }
+            seen.insert(values.clone(), *row);
+            candidates.push((*row, values));
+        }
+
+        let rows_per_query = (SEARCH_TERMS / keys.len().max(1)).max(1);
+        for chunk in candidates.chunks(rows_per_query) {
+            let mut terms: Vec<String> = Vec::new();
+            for (_, values) in chunk {
+                for (key, value) in keys.iter().zip(values.iter()) {
+                    let term = key.search_term(value);
+                    if !terms.contains(&term) {
+                        terms.push(term);
+                    }
+                }
+            }
+            let query = format!("type:ticket {}", terms.join(" "));
+            let found = client.search(&urls.search, &query).await?;
+            for (row, values) in chunk {
+                // search is fuzzy and mixes the rows' values, so only exact matches on
+                // every key count
+                let existing = found.iter().find(|found| {
+                    keys.iter()
+                        .zip(values.iter())
+                        .all(|(key, value)| key.value_in(found).as_ref() == Some(value))
+                });
+                if let Some(found) = existing {
+                    duplicates.push((*row, tr!(MatchesTicket, found["id"])));
+                }
+            }
+        }
+        duplicates.sort_by_key(|(row, _)| *row);
+        Ok(duplicates)
+    }
+}
+
+pub mod report {
+    use crate::api::JobStatus;
+    use crate::error::{CellError, ImportError};
+    use anyhow::{Context, Result};
+    use chrono::{DateTime, Utc};
+    use serde_json::Value;
+    use sha2::{Digest, Sha256};
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    static HUMAN_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+    /// Keeps stdout for the JSON summary of `--summary -`, messages meant for people
+    /// go to stderr from then on
+    pub fn human_output_to_stderr() {
+        HUMAN_TO_STDERR.store(true, Ordering::Relaxed);
+    }
+
+    pub fn human_output_is_stderr() -> bool {
+        HUMAN_TO_STDERR.load(Ordering::Relaxed)
+    }
+
+    /// What happened to each row of the sheet, printed once the run is over
+    #[derive(Debug, Default)]
+    pub struct RunReport {
+        /// Rows read from the sheet, blank ones aside
+        pub rows: u64,
+        pub created: Vec<(usize, u64)>,
+        pub updated: Vec<(usize, u64)>,
+        pub unchanged: Vec<usize>,
+        /// Rows that look like a ticket that was already imported, and why
+        pub duplicates: Vec<(usize, String)>,
+        pub duplicates_skipped: bool,
+        pub invalid_rows: Vec<(usize, String)>,
+        /// Every cell of the invalid rows that could not be converted
+        pub cell_errors: Vec<CellError>,
+        /// Rows left out by the worksheet filters
+        pub skipped: Vec<usize>,
+        pub failed: Vec<(usize, String)>,
+        pub job_ids: Vec<String>,
+    }
+
+    impl RunReport {
+        /// Matches a finished job's results with the rows that were sent in it, in order.
+        pub fn record_job(&mut self, job: &JobStatus, rows: &[usize], is_update: bool) {
+            self.job_ids.push(job.id.clone());
+            let results = job.results.as_deref().unwrap_or_default();
+            if results.is_empty() && job.status != "completed" {
+                let reason = ImportError::JobFailed {
+                    id: job.id.clone(),
+                    status: job.status.clone(),
+                    message: job.message.clone().unwrap_or_default(),
+                }
+                .to_string();
+                for row in rows {
+                    self.failed.push((*row, reason.clone()));
+                }
+                return;
+            }
+            let mut reported: HashMap<usize, bool> = HashMap::new();
+            for (position, result) in results.iter().enumerate() {
+                let row = match rows.get(result.index.unwrap_or(position)) {
+                    Some(row) => *row,
+                    None => continue,
+                };
+                reported.insert(row, true);
+                match (result.error.as_ref(), result.id) {
+                    (None, Some(id)) if result.success != Some(false) => {
+                        if is_update {
+                            self.updated.push((row, id));
+                        } else {
+                            self.created.push((row, id));
+                        }
+                    }
+                    (error, _) => {
+                        let reason = match (error, result.details.as_ref()) {
+                            (Some(error), Some(details)) => format!("{}: {}", error, details),
+                            (Some(error), None) => error.clone(),
+                            (None, Some(details)) => details.clone(),
+                            (None, None) => tr!(RejectedByZendesk).to_string(),
+                        };
+                        self.failed.push((row, reason));
+                    }
+                }
+            }
+            for row in rows.iter().filter(|row| !reported.contains_key(row)) {
+                self.failed.push((*row, tr!(MissingFromJob, job.id)));
+            }
+        }
+
+        /// Every report's rows in one, to count them. Line numbers from different
+        /// sheets are mixed up in it.
+        pub fn total<'a>(reports: impl Iterator<Item = &'a RunReport>) -> RunReport {
+            let mut total = RunReport::default();
+            for report in reports {
+                total.rows += report.rows;
+                total.created.extend(report.created.iter().cloned());
+                total.updated.extend(report.updated.iter().cloned());
+                total.unchanged.extend(report.unchanged.iter().cloned());
+                total.duplicates.extend(report.duplicates.iter().cloned());
+                total
+                    .invalid_rows
+                    .extend(report.invalid_rows.iter().cloned());
+                total.cell_errors.extend(report.cell_errors.iter().cloned());
+                total.skipped.extend(report.skipped.iter().cloned());
+                total.failed.extend(report.failed.iter().cloned());
+                total.job_ids.extend(report.job_ids.iter().cloned());
+            }
+            total
+        }
+
+        /// Rows that were invalid or rejected by Zendesk
+        pub fn has_failures(&self) -> bool {
+            !self.invalid_rows.is_empty() || !self.failed.is_empty()
+        }
+
+        fn counts_json(&self) -> Value {
+            json!({
+                "rows": self.rows,
+                "created": self.created.len(),
+                "updated": self.updated.len(),
+                "unchanged": self.unchanged.len(),
+                "duplicates": self.duplicates.len(),
+                "skipped": self.skipped.len(),
+                "invalid": self.invalid_rows.len(),
+                "rejected": self.failed.len(),
+            })
+        }
+
+        fn to_json(&self) -> Value {
+            let ids = |rows: &[(usize, u64)]| -> Vec<Value> {
+                rows.iter()
+                    .map(|(line, id)| json!({ "line": line, "id": id }))
+                    .collect()
+            };
+            let reasons = |rows: &[(usize, String)]| -> Vec<Value> {
+                rows.iter()
+                    .map(|(line, reason)| json!({ "line": line, "reason": reason }))
+                    .collect()
+            };
+            json!({
+                "totals": self.counts_json(),
+                "created": ids(&self.created),
+                "updated": ids(&self.updated),
+                "unchanged": self.unchanged,
+                "duplicates": reasons(&self.duplicates),
+                "skipped": self.skipped,
+                "invalid": reasons(&self.invalid_rows),
+                "cell_errors": self.cell_errors,
+                "rejected": reasons(&self.failed),
+                "job_ids": self.job_ids,
+            })
+        }
+
+        pub fn print(&self) {
+            let action = if self.duplicates_skipped {
+                tr!(DuplicateSkipped)
+            } else {
+                tr!(DuplicateImported)
+            };
+            for (row, reason) in self.duplicates.iter() {
+                eprintln!("{}", tr!(LineDuplicate, row, reason, action));
+            }
+            self.print_invalid();
+            for (row, reason) in self.failed.iter() {
+                eprintln!("{}", tr!(LineRejected, row, reason));
+            }
+            self.print_counts("");
+        }
+
+        /// Invalid rows with each of their bad cells on its own line
+        fn print_invalid(&self) {
+            let mut by_line: HashMap<usize, Vec<&CellError>> = HashMap::new();
+            for cell in self.cell_errors.iter() {
+                by_line.entry(cell.line).or_default().push(cell);
+            }
+            for (row, reason) in self.invalid_rows.iter() {
+                let cells = by_line.get(row).map_or(&[][..], Vec::as_slice);
+                match cells.first() {
+                    None => eprintln!("{}", tr!(LineInvalid, row, reason)),
+                    Some(first) => {
+                        eprintln!("{}", tr!(LineInvalidCells, row, first.sheet));
+                        for cell in cells {
+                            eprintln!("    {}", cell);
+                        }
+                    }
+                }
+            }
+        }
+
+        pub fn print_counts(&self, prefix: &str) {
+            say!(
+                "{}",
+                tr!(
+                    Counts,
+                    prefix,
+                    self.created.len(),
+                    self.updated.len(),
+                    self.unchanged.len(),
+                    self.duplicates.len(),
+                    self.skipped.len(),
+                    self.invalid_rows.len(),
+                    self.failed.len()
+                )
+            );
+        }
+    }
+
+    /// One worksheet's results along with where it came from
+    pub struct SheetReport {
+        pub file: PathBuf,
+        pub sheet: String,
+        pub report: RunReport,
+    }
+
+    /// What `--summary` writes, for scripts and audits
+    pub struct RunSummary<'a> {
+        pub mode: &'static str,
+        pub started: DateTime<Utc>,
+        pub duration: Duration,
+        pub api_calls: u64,
+        pub sheets: &'a [SheetReport],
+        pub total: &'a RunReport,
+        /// Why the run stopped early, if it did
+        pub error: Option<String>,
+    }
+
+    impl RunSummary<'_> {
+        pub fn write(&self, path: &Path) -> Result<()> {
+            let sheets = self
+                .sheets
+                .iter()
+                .map(|sheet| {
+                    let mut value = sheet.report.to_json();
+                    value["file"] = json!(sheet.file);
+                    value["sha256"] = json!(sha256(&sheet.file)?);
+                    value["sheet"] = json!(sheet.sheet);
+                    Ok(value)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let summary = json!({
+                "mode": self.mode,
+                "started_at": self.started.to_rfc3339(),
+                "duration_secs": self.duration.as_secs_f64(),
+                "api_calls": self.api_calls,
+                "totals": self.total.counts_json(),
+                "sheets": sheets,
+                "error": self.error,
+            });
+            let summary = serde_json::to_string_pretty(&summary)?;
+            if path == Path::new("-") {
+                println!("{}", summary);
+                return Ok(());
+            }
+            fs::write(path, summary).with_context(|| tr!(SummaryUnwritable, path.display()))
+        }
+    }
+
+    /// Hex digest of the workbook, to tell which version of a file was imported
+    fn sha256(path: &Path) -> Result<String> {
+        let content = fs::read(path).with_context(|| tr!(FileUnreadable, path.display()))?;
+        Ok(Sha256::digest(&content)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect())
+    }
+}
+
+pub mod filter {
+    use crate::objects::config::{FilterCondition, FilterValue, RowFilter};
+    use crate::objects::excel_mapper::{excel_column_to_index, excel_datetime};
+    use crate::objects::ticket::cell_to_string;
+    use anyhow::{Context, Result};
+    use calamine::DataType;
+    use chrono::{Duration, Local, NaiveDate};
+    use regex::Regex;
+
+    enum Rule {
+        Equals(usize, String),
+        NotEquals(usize, String),
+        OneOf(usize, Vec<String>),
+        Matches(usize, Regex),
+        NotEmpty(usize, bool),
+        GreaterThan(usize, Operand),
+        LessThan(usize, Operand),
+        Rows(usize, usize),
+    }
+
+    enum Operand {
+        Number(f64),
+        Date(NaiveDate),
+    }
+
+    /// `[[worksheet.filters]]` ready to be checked against rows, a row is imported
+    /// only when it matches every rule
+    pub struct RowFilters {
+        rules: Vec<Rule>,
+    }
+
+    impl RowFilters {
+        pub fn new(filters: &[RowFilter]) -> Result<Self> {
+            let rules = filters
+                .iter()
+                .enumerate()
+                .map(|(idx, filter)| Rule::new(filter).with_context(|| tr!(BadFilter, idx + 1)))
+                .collect::<Result<_>>()?;
+            Ok(RowFilters { rules })
+        }
+
+        /// `row_num` is the 1-based line number in the sheet
+        pub fn matches(&self, row_num: usize, row: &[DataType]) -> bool {
+            self.rules.iter().all(|rule| rule.matches(row_num, row))
+        }
+    }
+
+    impl Rule {
+        fn new(filter: &RowFilter) -> Result<Self> {
+            if let FilterCondition::Rows([first, last]) = filter.condition {
+                return Ok(Rule::Rows(first, last));
+            }
+            let column = filter
+                .column
+                .as_deref()
+                .and_then(excel_column_to_index)
+                .ok_or_else(|| anyhow!(tr!(FilterNeedsColumn)))?;
+            Ok(match &filter.condition {
+                FilterCondition::Equals(value) => Rule::Equals(column, value.clone()),
+                FilterCondition::NotEquals(value) => Rule::NotEquals(column, value.clone()),
+                FilterCondition::OneOf(values) => Rule::OneOf(column, values.clone()),
+                FilterCondition::Matches(pattern) => Rule::Matches(
+                    column,
+                    Regex::new(pattern).with_context(|| tr!(BadFilterRegex, pattern))?,
+                ),
+                FilterCondition::NotEmpty(not_empty) => Rule::NotEmpty(column, *not_empty),
+                FilterCondition::GreaterThan(value) => Rule::GreaterThan(column, operand(value)?),
+                FilterCondition::LessThan(value) => Rule::LessThan(column, operand(value)?),
+                FilterCondition::Rows(_) => unreachable!(),
+            })
+        }
+
+        fn matches(&self, row_num: usize, row: &[DataType]) -> bool {
+            let text = |column: &usize| row.get(*column).and_then(cell_text);
+            match self {
+                Rule::Equals(column, value) => text(column).as_ref() == Some(value),
+                Rule::NotEquals(column, value) => text(column).as_ref() != Some(value),
+                Rule::OneOf(column, values) => text(column).is_some_and(|t| values.contains(&t)),
+                Rule::Matches(column, regex) => text(column).is_some_and(|t| regex.is_match(&t)),
+                Rule::NotEmpty(column, not_empty) => text(column).is_some() == *not_empty,
+                Rule::GreaterThan(column, operand) => {
+                    compare(row.get(*column), operand).is_some_and(|o| o.is_gt())
+                }
+                Rule::LessThan(column, operand) => {
+                    compare(row.get(*column), operand).is_some_and(|o| o.is_lt())
+                }
+                Rule::Rows(first, last) => (*first..=*last).contains(&row_num),
+            }
+        }
+    }
+
+    fn operand(value: &FilterValue) -> Result<Operand> {
+        match value {
+            FilterValue::Number(number) => Ok(Operand::Number(*number)),
+            FilterValue::Date(date) => parse_date(date)
+                .map(Operand::Date)
+                .ok_or_else(|| anyhow!(tr!(FilterNotADate, date))),
+        }
+    }
+
+    /// "2020-12-31", "31/12/2020", "today" or "today-30"
+    fn parse_date(text: &str) -> Option<NaiveDate> {
+        let text = text.trim();
+        if let Some(offset) = text.strip_prefix("today") {
+            let days: i64 = match offset.replace(' ', "").as_str() {
+                "" => 0,
+                offset => offset.parse().ok()?,
+            };
+            return Some(Local::today().naive_local() + Duration::days(days));
+        }
+        NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .or_else(|_| NaiveDate::parse_from_str(text, "%d/%m/%Y"))
+            .ok()
+    }
+
+    fn cell_text(cell: &DataType) -> Option<String> {
+        match cell {
+            DataType::Bool(b) => Some(b.to_string()),
+            cell => cell_to_string(cell),
+        }
+    }
+
+    fn compare(cell: Option<&DataType>, operand: &Operand) -> Option<std::cmp::Ordering> {
+        let cell = cell?;
+        match operand {
+            Operand::Number(number) => {
+                let value = match cell {
+                    DataType::Float(f) => *f,
+                    DataType::Int(i) => *i as f64,
+                    DataType::String(s) => s.trim().parse().ok()?,
+                    _ => return None,
+                };
+                value.partial_cmp(number)
+            }
+            Operand::Date(date) => {
+                let value = match cell {
+                    // numbers far outside the calendar, such as a CPF, match nothing
+                    DataType::Float(serial) => excel_datetime(*serial)?.date(),
+                    DataType::String(s) => parse_date(s)?,
+                    _ => return None,
+                };
+                Some(value.cmp(date))
+            }
+        }
+    }
+}
+
+pub mod init {
+    use crate::api::{TicketField, ZendeskClient};
+    use crate::objects::config::{ApiUrls, Config, Credentials, HttpSettings, Secret};
+    use crate::objects::excel_mapper::{excel_column_to_index, index_to_excel_column};
+    use crate::validation::{MIN_SCORE, SUPPORTED_TYPES};
+    use crate::{InitOpt, Opt};
+    use anyhow::{Context, Result};
+    use calamine::{open_workbook_auto, DataType, Reader};
+    use std::fmt::Write as FmtWrite;
+    use std::fs;
+    use std::io::{self, Write};
+
+    const TIMEZONES: [&str; 4] = ["Acre", "DeNoronha", "East", "West"];
+
+    /// System fields and the header names they are usually found under.
+    const SYSTEM_FIELDS: [(&str, &[&str]); 6] = [
+        (
+            "comment",
+            &[
+                "comment",
+                "comentario",
+                "description",
+                "descricao",
+                "mensagem",
+            ],
+        ),
+        ("subject", &["subject", "assunto", "titulo"]),
+        ("status", &["status", "situacao", "estado"]),
+        ("priority", &["priority", "prioridade"]),
+        (
+            "tickettype",
+            &["type", "ticket type", "tipo", "tipo de ticket"],
+        ),
+        ("assignee", &["assignee", "responsavel", "atribuido a"]),
+    ];
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Target {
+        System(&'static str),
+        Custom(String),
+    }
+
+    impl Target {
+        fn name(&self) -> &str {
+            match self {
+                Target::System(name) => name,
+                Target::Custom(title) => title,
+            }
+        }
+    }
+
+    struct Header {
+        column: usize,
+        name: String,
+        proposal: Option<(Target, f64)>,
+    }
+
+    pub async fn run(opt: &Opt, init_opt: &InitOpt) -> Result<()> {
+        let output = init_opt
+            .output
+            .clone()
+            .or_else(|| opt.config_path.clone())
+            .unwrap_or_else(Config::get_default_path);
+        // the existing config may hold credentials, --yes alone doesn't replace it
+        if output.exists() && !init_opt.force {
+            if init_opt.yes {
+                return Err(anyhow!(tr!(ConfigExists, output.display())));
+            }
+            if !confirm(&tr!(OverwriteConfig, output.display()), false)? {
+                return Ok(());
+            }
+        }
+
+        let (sheet, mut headers) = read_headers(init_opt)?;
+        let credentials = ask_credentials(opt)?;
+        let urls = ApiUrls::default();
+        let client = ZendeskClient::new(&credentials, &HttpSettings::default())?;
+        let api_fields = client.get_api_fields(&urls.get_fields).await?;
+
+        let targets = candidate_targets(&api_fields);
+        propose_mapping(&mut headers, &targets);
+        let mut mapping = if init_opt.yes {
+            headers
+                .iter()
+                .filter_map(|h| h.proposal.clone().map(|(target, _)| (h.column, target)))
+                .collect()
+        } else {
+            confirm_mapping(&headers, &targets)?
+        };
+
+        if !mapping.iter().any(|(_, t)| *t == Target::System("comment")) {
+            if init_opt.yes {
+                return Err(anyhow!(tr!(NoCommentColumn)));
+            }
+            let column = loop {
+                let answer = ask(tr!(AskCommentColumn))?;
+                match excel_column_to_index(&answer) {
+                    Some(column) => break column,
+                    _ => println!("{}", tr!(NotAColumnLetter, answer)),
+                }
+            };
+            mapping.retain(|(c, _)| *c != column);
+            mapping.push((column, Target::System("comment")));
+        }
+
+        let timezone = if init_opt.yes {
+            "East".to_string()
+        } else {
+            loop {
+                let answer = ask(tr!(AskTimezone))?;
+                if answer.is_empty() {
+                    break "East".to_string();
+                }
+                if TIMEZONES.contains(&answer.as_str()) {
+                    break answer;
+                }
+                println!("{}", tr!(CellUnknownTimezone, answer));
+            }
+        };
+
+        let content = render_config(
+            &urls,
+            &credentials,
+            &sheet,
+            init_opt.header_row + 1,
+            &timezone,
+            &mapping,
+        );
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| tr!(DirectoryUncreatable, parent.display()))?;
+        }
+        fs::write(&output, content).with_context(|| tr!(ConfigUnwritable, output.display()))?;
+        println!("{}", tr!(ConfigWritten, output.display()));
+        println!("{}", tr!(TokenFromEnv));
+        Ok(())
+    }
+
+    fn read_headers(init_opt: &InitOpt) -> Result<(String, Vec<Header>)> {
+        if init_opt.header_row == 0 {
+            return Err(anyhow!(tr!(HeaderRowZero)));
+        }
+        let mut workbook = open_workbook_auto(&init_opt.workbook)
+            .with_context(|| tr!(WorkbookUnopenable, init_opt.workbook.display()))?;
+        let sheet = match &init_opt.sheet {
+            Some(sheet) => sheet.clone(),
+            None => workbook
+                .sheet_names()
+                .first()
+                .cloned()
+                .ok_or_else(|| anyhow!(tr!(NoWorksheets, init_opt.workbook.display())))?,
+        };
+        let range = workbook
+            .worksheet_range(&sheet)
+            .with_context(|| tr!(WorksheetNotFound, sheet))??;
+        let row = (init_opt.header_row - 1) as u32;
+        let last_column = range.end().map(|(_, col)| col).unwrap_or(0);
+        let headers: Vec<Header> = (0..=last_column)
+            .filter_map(|col| {
+                let name = match range.get_value((row, col))? {
+                    DataType::Empty => return None,
+                    DataType::String(s) => s.trim().to_string(),
+                    other => other.to_string(),
+                };
+                if name.is_empty() {
+                    return None;
+                }
+                Some(Header {
+                    column: col as usize,
+                    name,
+                    proposal: None,
+                })
+            })
+            .collect();
+        if headers.is_empty() {
+            return Err(anyhow!(tr!(HeaderRowEmpty, init_opt.header_row, sheet)));
+        }
+        Ok((sheet, headers))
+    }
+
+    fn ask_credentials(opt: &Opt) -> Result<Credentials> {
+        let subdomain = match &opt.subdomain {
+            Some(subdomain) => subdomain.clone(),
+            None => ask(tr!(AskSubdomain))?,
+        };
+        let email = match &opt.email {
+            Some(email) => email.clone(),
+            None => ask(tr!(AskEmail))?,
+        };
+        let api_token = match &opt.api_token {
+            Some(api_token) => api_token.clone(),
+            None => Secret::from(
+                rpassword::read_password_from_tty(Some(tr!(AskApiToken)))
+                    .with_context(|| tr!(TokenUnreadable))?
+                    .trim(),
+            ),
+        };
+        Ok(Credentials {
+            token_source: None,
+            api_token,
+            email,
+            subdomain,
+        })
+    }
+
+    fn candidate_targets(api_fields: &[TicketField]) -> Vec<(Target, Vec<String>)> {
+        let system = SYSTEM_FIELDS.iter().map(|(name, synonyms)| {
+            (
+                Target::System(name),
+                synonyms.iter().map(|s| s.to_string()).collect(),
+            )
+        });
+        let custom = api_fields
+            .iter()
+            .filter(|f| SUPPORTED_TYPES.contains(&f.field_type.as_str()))
+            .map(|f| (Target::Custom(f.title.clone()), vec![normalize(&f.title)]));
+        system.chain(custom).collect()
+    }
+
+    fn normalize(text: &str) -> String {
+        deunicode::deunicode(text).trim().to_lowercase()
+    }
+
+    fn score(header: &str, names: &[String]) -> f64 {
+        let header = normalize(header);
+        names
+            .iter()
+            .map(|name| strsim::normalized_levenshtein(&header, name))
+            .fold(0.0, f64::max)
+    }
+
+    /// Greedily pairs headers and fields, best matches first, so that a field is
+    /// never proposed for two columns.
+    fn propose_mapping(headers: &mut [Header], targets: &[(Target, Vec<String>)]) {
+        let mut scores = Vec::new();
+        for (h, header) in headers.iter().enumerate() {
+            for (t, (_, names)) in targets.iter().enumerate() {
+                let score = score(&header.name, names);
+                if score >= MIN_SCORE {
+                    scores.push((score, h, t));
+                }
+            }
+        }
+        scores.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+        let mut taken = vec![false; targets.len()];
+        for (score, h, t) in scores {
+            if taken[t] || headers[h].proposal.is_some() {
+                continue;
+            }
+            taken[t] = true;
+            headers[h].proposal = Some((targets[t].0.clone(), score));
+        }
+    }
+
+    fn confirm_mapping(
+        headers: &[Header],
+        targets: &[(Target, Vec<String>)],
+    ) -> Result<Vec<(usize, Target)>> {
+        println!("{}", tr!(MappingHelp));
+        let mut mapping: Vec<(usize, Target)> = Vec::new();
+        for header in headers {
+            let column = index_to_excel_column(header.column);
+            let question = match &header.proposal {
+                Some((target, score)) => tr!(
+                    MappingProposal,
+                    format!("{:>3}", column),
+                    header.name,
+                    target.name(),
+                    format!("{:.0}", score * 100.0)
+                ),
+                None => tr!(MappingNoMatch, format!("{:>3}", column), header.name),
+            };
+            loop {
+                let answer = ask(&question)?;
+                let target = match answer.to_lowercase().as_str() {
+                    "" | "y" | "yes" | "s" | "sim" => header.proposal.clone().map(|(t, _)| t),
+                    "n" | "no" | "nao" | "não" | "skip" | "pular" => None,
+                    _ => match targets
+                        .iter()
+                        .find(|(t, _)| t.name().eq_ignore_ascii_case(&answer))
+                    {
+                        Some((target, _)) => Some(target.clone()),
+                        None => {
+                            println!("{}", tr!(MappingUnknownField, answer));
+                            continue;
+                        }
+                    },
+                };
+                if let Some(target) = target {
+                    if let Some((other, _)) = mapping.iter().find(|(_, t)| *t == target) {
+                        println!(
+                            "{}",
+                            tr!(MappingTaken, target.name(), index_to_excel_column(*other))
+                        );
+                        continue;
+                    }
+                    mapping.push((header.column, target));
+                }
+                break;
+            }
+        }
+        Ok(mapping)
+    }
+
+    fn render_config(
+        urls: &ApiUrls,
+        credentials: &Credentials,
+        sheet: &str,
+        top_row: usize,
+        timezone: &str,
+        mapping: &[(usize, Target)],
+    ) -> String {
+        let quote = |s: &str| toml::Value::String(s.to_string()).to_string();
+        let mut out = String::new();
+        writeln!(out, "[api_url]").unwrap();
+        writeln!(out, "get_fields = {}", quote(&urls.get_fields)).unwrap();
+        writeln!(out, "post_many = {}", quote(&urls.post_many)).unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "[credentials]").unwrap();
+        writeln!(out, "api_token = {{ env = \"ZENDESK_API_TOKEN\" }}").unwrap();
+        writeln!(out, "email = {}", quote(&credentials.email)).unwrap();
+        writeln!(out, "subdomain = {}", quote(&credentials.subdomain)).unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "[worksheet]").unwrap();
+        writeln!(out, "name = {}", quote(sheet)).unwrap();
+        writeln!(out, "top_row = {}", top_row).unwrap();
+        writeln!(out, "# must be one of {{ Acre, DeNoronha, East, West }}").unwrap();
+        writeln!(out, "timezone = {}", quote(timezone)).unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "# fields are case sensitive").unwrap();
+        writeln!(out, "[ticket]").unwrap();
+        writeln!(out, "    [ticket.system_fields]").unwrap();
+        for (name, _) in SYSTEM_FIELDS.iter() {
+            let column = mapping
+                .iter()
+                .find(|(_, t)| *t == Target::System(name))
+                .map(|(c, _)| index_to_excel_column(*c))
+                .unwrap_or_default();
+            writeln!(out, "        {} = {}", name, quote(&column)).unwrap();
+        }
+        writeln!(out, "    [ticket.custom_fields]").unwrap();
+        let mut custom: Vec<_> = mapping
+            .iter()
+            .filter_map(|(c, t)| match t {
+                Target::Custom(title) => Some((*c, title)),
+                Target::System(_) => None,
+            })
+            .collect();
+        custom.sort();
+        for (column, title) in custom {
+            writeln!(
+                out,
+                "        {} = {}",
+                quote(title),
+                quote(&index_to_excel_column(column))
+            )
+            .unwrap();
+        }
+        out
+    }
+
+    fn ask(question: &str) -> Result<String> {
+        print!("{}", question);
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer)? == 0 {
+            return Err(anyhow!(tr!(EndOfInput)));
+        }
+        Ok(answer.trim().to_string())
+    }
+
+    fn confirm(question: &str, default: bool) -> Result<bool> {
+        let hint = if default {
+            tr!(ConfirmYes)
+        } else {
+            tr!(ConfirmNo)
+        };
+        let answer = ask(&format!("{} {} ", question, hint))?;
+        Ok(match answer.to_lowercase().as_str() {
+            "" => default,
+            "y" | "yes" | "s" | "sim" => true,
+            _ => false,
+        })
+    }
+}
+
+pub mod fields {
+    use crate::api::{TicketField, ZendeskClient};
+    use crate::objects::config::ApiConfig;
+    use crate::{FieldsOpt, Opt};
+    use anyhow::Result;
+
+    pub async fn run(opt: &mut Opt, fields_opt: &FieldsOpt) -> Result<()> {
+        let config = ApiConfig::from_opt(opt)?;
+        let client = ZendeskClient::new(&config.credentials, &config.http)?;
+        let fields: Vec<TicketField> = client
+            .get_api_fields(&config.urls.get_fields)
+            .await?
+            .into_iter()
+            .filter(|field| fields_opt.all || field.active)
+            .collect();
+        if fields_opt.json {
+            println!("{}", serde_json::to_string_pretty(&fields)?);
+        } else {
+            print_table(&fields);
+        }
+        Ok(())
+    }
+
+    /// Widest of the header and the values below it, in characters
+    fn width(header: &str, values: impl Iterator<Item = usize>) -> usize {
+        values.fold(header.chars().count(), usize::max)
+    }
+
+    fn print_table(fields: &[TicketField]) {
+        let yes_no = |flag: bool| if flag { tr!(Yes) } else { tr!(No) };
+        let id_width = width(tr!(FieldsId), fields.iter().map(|f| f.id.to_string().len()));
+        let title_width = width(
+            tr!(FieldsTitle),
+            fields.iter().map(|f| f.title.chars().count()),
+        );
+        let type_width = width(tr!(FieldsType), fields.iter().map(|f| f.field_type.len()));
+        let active_width = width(
+            tr!(FieldsActive),
+            [tr!(Yes), tr!(No)].iter().map(|text| text.chars().count()),
+        );
+        println!(
+            "{:<iw$}  {:<tw$}  {:<yw$}  {:<aw$}  {}",
+            tr!(FieldsId),
+            tr!(FieldsTitle),
+            tr!(FieldsType),
+            tr!(FieldsActive),
+            tr!(FieldsRequired),
+            iw = id_width,
+            tw = title_width,
+            yw = type_width,
+            aw = active_width
+        );
+        for field in fields {
+            println!(
+                "{:<iw$}  {:<tw$}  {:<yw$}  {:<aw$}  {}",
+                field.id,
+                field.title,
+                field.field_type,
+                yes_no(field.active),
+                yes_no(field.required),
+                iw = id_width,
+                tw = title_width,
+                yw = type_width,
+                aw = active_width
+            );
+            for option in field.custom_field_options.iter().flatten() {
+                println!(
+                    "{:<iw$}      {} -> {}",
+                    "",
+                    option.name,
+                    option.value,
+                    iw = id_width
+                );
+            }
+        }
+    }
+}
+
+pub mod validation {
+    use crate::api::TicketField;
+    use crate::error::{ImportError, RowErrors};
+    use crate::objects::config::Config;
+    use crate::objects::excel_mapper::{index_to_excel_column, FieldRef};
+    use crate::objects::ticket::literal_errors;
+    use std::collections::HashMap;
+
+    /// How close a name has to be to a field title, 0 to 1, to be offered as a match
+    pub const MIN_SCORE: f64 = 0.6;
+
+    /// Field types `Ticket::from_row` knows how to convert
+    pub const SUPPORTED_TYPES: [&str; 6] =
+        ["integer", "decimal", "date", "checkbox", "text", "tagger"];
+
+    /// Zendesk system field types and the `[ticket.system_fields]` entry that fills them in
+    const SYSTEM_TYPES: [(&str, &str); 6] = [
+        ("subject", "subject"),
+        ("description", "comment"),
+        ("status", "status"),
+        ("tickettype", "tickettype"),
+        ("priority", "priority"),
+        ("assignee", "assignee"),
+    ];
+
+    /// Zendesk system field types with no `[ticket.system_fields]` entry, Zendesk fills
+    /// them in itself (group routing, the status' custom status) so they aren't reported
+    const UNMAPPED_SYSTEM_TYPES: [&str; 2] = ["group", "custom_status"];
+
+    /// Checks the mapping in `config` against the account's ticket fields and the sheet,
+    /// returning one message per problem found. Required fields only matter when
+    /// `creates_tickets` is set, updates leave unmapped fields alone. Literal values that
+    /// don't fit their field fail outright, `--allow-partial` or not, as no row could
+    /// be imported with them.
+    pub fn validate(
+        config: &Config,
+        api_fields: &[TicketField],
+        last_column: usize,
+        creates_tickets: bool,
+    ) -> Result<Vec<String>, ImportError> {
+        let literals = literal_errors(config, api_fields);
+        if !literals.is_empty() {
+            return Err(ImportError::Config(tr!(
+                LiteralInvalid,
+                RowErrors(literals)
+            )));
+        }
+        let mut issues = Vec::new();
+        let system_fields = config.ticket.system_fields.columns();
+        let sources = config.ticket.system_fields.sources();
+        let custom_fields = &config.ticket.custom_fields;
+        let custom_names: Vec<String> = custom_fields
+            .iter()
+            .map(|m| match &m.field {
+                FieldRef::Title(title) => title.clone(),
+                FieldRef::Id(_) => m
+                    .field
+                    .find(api_fields)
+                    .map_or_else(|| m.field.to_string(), |f| f.title.clone()),
+            })
+            .collect();
+
+        for mapping in custom_fields.iter() {
+            match mapping.field.find(api_fields) {
+                None => issues.push(unknown_field(&mapping.field, api_fields)),
+                Some(field) if !field.active => issues.push(tr!(FieldInactive, mapping.field)),
+                Some(field) if !SUPPORTED_TYPES.contains(&field.field_type.as_str()) => {
+                    issues.push(tr!(FieldTypeUnsupported, mapping.field, field.field_type))
+                }
+                Some(_) => {}
+            }
+        }
+
+        let mut by_column: HashMap<usize, Vec<&str>> = HashMap::new();
+        let mapped = system_fields
+            .iter()
+            .map(|(name, column)| (*name, *column))
+            .chain(
+                custom_fields
+                    .iter()
+                    .zip(custom_names.iter())
+                    .filter_map(|(mapping, name)| Some((name.as_str(), mapping.source.column?))),
+            );
+        for (name, column) in mapped {
+            by_column.entry(column).or_default().push(name);
+        }
+        let mut columns: Vec<_> = by_column.into_iter().collect();
+        columns.sort();
+        for (column, names) in columns {
+            if names.len() > 1 {
+                issues.push(tr!(
+                    ColumnMappedTwice,
+                    index_to_excel_column(column),
+                    names.join(", ")
+                ));
+            }
+            if column > last_column {
+                issues.push(tr!(
+                    ColumnBeyondSheet,
+                    index_to_excel_column(column),
+                    names.join(", "),
+                    index_to_excel_column(last_column)
+                ));
+            }
+        }
+
+        let required = api_fields
+            .iter()
+            .filter(|f| creates_tickets && f.active && f.required)
+            .filter(|f| !UNMAPPED_SYSTEM_TYPES.contains(&f.field_type.as_str()));
+        for field in required {
+            let is_mapped = match SYSTEM_TYPES.iter().find(|(t, _)| *t == field.field_type) {
+                Some((_, name)) => sources.iter().any(|(n, _)| n == name),
+                None => custom_fields.iter().any(|m| m.field.matches(field)),
+            };
+            if !is_mapped {
+                issues.push(tr!(FieldRequired, field.title));
+            }
+        }
+        Ok(issues)
+    }
+
+    fn unknown_field(field: &FieldRef, api_fields: &[TicketField]) -> String {
+        let title = match field {
+            FieldRef::Title(title) => title,
+            FieldRef::Id(id) => return tr!(UnknownFieldId, id),
+        };
+        let lowercase = title.to_lowercase();
+        let mut suggestions: Vec<(f64, &str)> = api_fields
+            .iter()
+            .map(|f| {
+                let score = strsim::normalized_levenshtein(&lowercase, &f.title.to_lowercase());
+                (score, f.title.as_str())
+            })
+            .filter(|(score, _)| *score >= MIN_SCORE)
+            .collect();
+        suggestions.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+        let suggestions: Vec<String> = suggestions
+            .iter()
+            .take(3)
+            .map(|(_, t)| format!("{:?}", t))
+            .collect();
+        if suggestions.is_empty() {
+            tr!(UnknownField, title)
+        } else {
+            tr!(UnknownFieldSuggestions, title, suggestions.join(tr!(Or)))
+        }
+    }
+}
+
+pub mod export {
+    use crate::api::{TicketField, ZendeskClient};
+    use crate::objects::config::Config;
+    use crate::ExportOpt;
+    use anyhow::{Context, Result};
+    use chrono::{Datelike, NaiveDate};
+    use rust_xlsxwriter::{ExcelDateTime, Format, Workbook};
+    use serde_json::Value;
+    use std::path::Path;
+
+    /// Where a column's value comes from in a ticket returned by the API
+    enum Source<'a> {
+        System(&'static str),
+        Custom(&'a TicketField),
+    }
+
+    struct Column<'a> {
+        index: usize,
+        header: String,
+        source: Source<'a>,
+    }
+
+    enum Cell {
+        Empty,
+        Text(String),
+        Number(f64),
+        Bool(bool),
+        Date(NaiveDate),
+    }
+
+    pub async fn run(config: &Config, export_opt: &ExportOpt) -> Result<()> {
+        let client = ZendeskClient::new(&config.credentials, &config.http)?;
+        let api_fields = client.get_api_fields(&config.urls.get_fields).await?;
+        let columns = columns(config, &api_fields);
+        let tickets = match (&export_opt.query, export_opt.view) {
+            (_, Some(view)) => {
+                let path = format!("{}/{}/tickets.json", config.urls.views, view);
+                client.get_all_pages(&path, &[], "tickets").await?
+            }
+            (Some(query), None) => {
+                let query = [("query", query.as_str()), ("filter[type]", "ticket")];
+                client
+                    .get_all_pages(&config.urls.search_export, &query, "results")
+                    .await?
+            }
+            (None, None) => return Err(anyhow!(tr!(NoExportSource))),
+        };
+        let rows: Vec<Vec<(usize, Cell)>> = tickets
+            .iter()
+            .map(|ticket| {
+                columns
+                    .iter()
+                    .map(|column| (column.index, cell(ticket, &column.source)))
+                    .collect()
+            })
+            .collect();
+
+        let output = &export_opt.output;
+        let extension = output
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("xlsx") => write_xlsx(output, config, &columns, &rows)?,
+            Some("csv") => write_csv(output, config, &columns, &rows)?,
+            _ => return Err(anyhow!(tr!(BadExportExtension))),
+        }
+        println!("{}", tr!(Exported, rows.len(), output.display()));
+        if extension.as_deref() == Some("csv") {
+            println!("{}", tr!(CsvNotImportable));
+        }
+        Ok(())
+    }
+
+    fn columns<'a>(config: &Config, api_fields: &'a [TicketField]) -> Vec<Column<'a>> {
+        let system = config
+            .ticket
+            .system_fields
+            .columns()
+            .into_iter()
+            .map(|(name, index)| Column {
+                index,
+                header: name.to_string(),
+                source: Source::System(name),
+            });
+        let custom = config.ticket.custom_fields.iter().filter_map(|mapping| {
+            let field = mapping.field.find(api_fields)?;
+            Some(Column {
+                index: mapping.source.column?,
+                header: field.title.clone(),
+                source: Source::Custom(field),
+            })
+        });
+        let mut columns: Vec<Column> = system.chain(custom).collect();
+        columns.sort_by_key(|c| c.index);
+        columns
+    }
+
+    fn cell(ticket: &Value, source: &Source) -> Cell {
+        match source {
+            Source::System(name) => {
+                let key = match *name {
+                    "comment" => "description",
+                    "tickettype" => "type",
+                    "assignee" => "assignee_id",
+                    other => other,
+                };
+                match ticket.get(key) {
+                    Some(Value::String(s)) => Cell::Text(s.clone()),
+                    Some(Value::Number(n)) => Cell::Text(n.to_string()),
+                    _ => Cell::Empty,
+                }
+            }
+            Source::Custom(field) => {
+                let value = ticket
+                    .get("custom_fields")
+                    .and_then(Value::as_array)
+                    .and_then(|fields| {
+                        fields
+                            .iter()
+                            .find(|f| f.get("id").and_then(Value::as_u64) == Some(field.id as u64))
+                    })
+                    .and_then(|f| f.get("value"));
+                custom_cell(value, field)
+            }
+        }
+    }
+
+    /// Turns an API value back into what `Ticket::from_row` expects to read, e.g.
+    /// dropdown tags become their display names again
+    fn custom_cell(value: Option<&Value>, field: &TicketField) -> Cell {
+        let value = match value {
+            None | Some(Value::Null) => return Cell::Empty,
+            Some(value) => value,
+        };
+        let text = match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        match field.field_type.as_str() {
+            "checkbox" => Cell::Bool(value.as_bool().unwrap_or(false)),
+            "integer" | "decimal" => text
+                .parse::<f64>()
+                .map(Cell::Number)
+                .unwrap_or(Cell::Text(text)),
+            "date" => NaiveDate::parse_from_str(&text, "%Y-%m-%d")
+                .map(Cell::Date)
+                .unwrap_or(Cell::Text(text)),
+            "tagger" => field
+                .custom_field_options
+                .iter()
+                .flatten()
+                .find(|option| option.value.to_string() == text)
+                .map(|option| Cell::Text(option.name.clone()))
+                .unwrap_or(Cell::Text(text)),
+            _ => Cell::Text(text),
+        }
+    }
+
+    /// The header goes right above the first ticket row, when there is room for it
+    fn header_row(config: &Config) -> Option<usize> {
+        config.worksheet.top_row.checked_sub(2)
+    }
+
+    fn write_xlsx(
+        output: &Path,
+        config: &Config,
+        columns: &[Column],
+        rows: &[Vec<(usize, Cell)>],
+    ) -> Result<()> {
+        let mut workbook = Workbook::new();
+        let bold = Format::new().set_bold();
+        let date = Format::new().set_num_format("yyyy-mm-dd");
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(
+            config
+                .worksheet
+                .name
+                .first()
+                .map_or("Tickets", String::as_str),
+        )?;
+        if let Some(header_row) = header_row(config) {
+            for column in columns {
+                worksheet.write_string_with_format(
+                    header_row as u32,
+                    column.index as u16,
+                    column.header.as_str(),
+                    &bold,
+                )?;
+            }
+        }
+        for (offset, row) in rows.iter().enumerate() {
+            let row_num = (config.worksheet.top_row.max(1) - 1 + offset) as u32;
+            for (column, cell) in row {
+                let column = *column as u16;
+                match cell {
+                    Cell::Empty => {}
+                    Cell::Text(text) => {
+                        worksheet.write_string(row_num, column, text.as_str())?;
+                    }
+                    Cell::Number(number) => {
+                        worksheet.write_number(row_num, column, *number)?;
+                    }
+                    Cell::Bool(flag) => {
+                        worksheet.write_boolean(row_num, column, *flag)?;
+                    }
+                    Cell::Date(day) => {
+                        let day = ExcelDateTime::from_ymd(
+                            day.year() as u16,
+                            day.month() as u8,
+                            day.day() as u8,
+                        )?;
+                        worksheet.write_datetime_with_format(row_num, column, &day, &date)?;
+                    }
+                }
+            }
+        }
+        workbook
+            .save(output)
+            .with_context(|| tr!(OutputUnwritable, output.display()))?;
+        Ok(())
+    }
+
+    /// For other tools only, the importer reads workbooks and not CSV, so the file has
+    /// to be saved as .xlsx before it can be imported again
+    fn write_csv(
+        output: &Path,
+        config: &Config,
+        columns: &[Column],
+        rows: &[Vec<(usize, Cell)>],
+    ) -> Result<()> {
+        let width = columns.iter().map(|c| c.index + 1).max().unwrap_or(0);
+        let mut writer = csv::Writer::from_path(output)
+            .with_context(|| tr!(OutputUnwritable, output.display()))?;
+        let empty = vec![String::new(); width];
+        if let Some(header_row) = header_row(config) {
+            for _ in 0..header_row {
+                writer.write_record(&empty)?;
+            }
+            let mut header = empty.clone();
+            for column in columns {
+                header[column.index] = column.header.clone();
+            }
+            writer.write_record(&header)?;
+        }
+        for row in rows {
+            let mut record = empty.clone();
+            for (column, cell) in row {
+                record[*column] = match cell {
+                    Cell::Empty => String::new(),
+                    Cell::Text(text) => text.clone(),
+                    Cell::Number(number) => number.to_string(),
+                    Cell::Bool(flag) => flag.to_string(),
+                    Cell::Date(day) => day.to_string(),
+                };
+            }
+            writer.write_record(&record)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+pub mod error {
+    use serde::{Serialize, Serializer};
+    use std::fmt;
+    use std::path::PathBuf;
+    use thiserror::Error;
+
+    /// Everything that stops an import or leaves rows out of it, for callers that need
+    /// to tell the cases apart, e.g. to decide whether the rest of the run can go on.
+    #[derive(Debug, Error)]
+    pub enum ImportError {
+        /// The config file, the command line options or the field mapping
+        Config(String),
+        /// A workbook or worksheet that could not be opened or read
+        SourceRead { path: PathBuf, message: String },
+        /// A row with cells that could not be converted
+        InvalidRow(RowErrors),
+        /// Zendesk could not be reached or its answer could not be read
+        Http {
+            context: String,
+            source: reqwest::Error,
+        },
+        /// Zendesk refused the request, usually because of what was in it
+        Api { status: u16, body: String },
+        /// Zendesk refused a bulk request for its size, sending fewer tickets at a time
+        /// should get through
+        PayloadTooLarge,
+        /// A ticket that could not be turned into the JSON sent to Zendesk
+        TicketEncoding(#[source] serde_json::Error),
+        /// A bulk job that failed as a whole, without results for its tickets
+        JobFailed {
+            id: String,
+            status: String,
+            message: String,
+        },
+    }
+
+    impl fmt::Display for ImportError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ImportError::Config(message) => f.write_str(message),
+                ImportError::SourceRead { path, message } => {
+                    f.write_str(&tr!(SourceRead, path.display(), message))
+                }
+                ImportError::InvalidRow(errors) => write!(f, "{}", errors),
+                ImportError::Http { context, source } => write!(f, "{}: {}", context, source),
+                ImportError::Api { status, body } => f.write_str(&tr!(ApiAnswered, status, body)),
+                ImportError::PayloadTooLarge => f.write_str(tr!(PayloadTooLarge)),
+                ImportError::TicketEncoding(source) => f.write_str(&tr!(TicketEncoding, source)),
+                ImportError::JobFailed {
+                    id,
+                    status,
+                    message,
+                } => f.write_str(&tr!(JobFailed, id, status, message)),
+            }
+        }
+    }
+
+    impl ImportError {
+        /// Wraps a reqwest error with what was being done when it happened
+        pub fn http(context: impl Into<String>) -> impl FnOnce(reqwest::Error) -> ImportError {
+            let context = context.into();
+            move |source| ImportError::Http { context, source }
+        }
+    }
+
+    /// Why a cell could not be turned into its field's value
+    #[derive(Debug, Clone, PartialEq, Error)]
+    pub enum ConversionError {
+        Empty,
+        NotText(String),
+        NotANumber(String),
+        NotAnInteger(String),
+        OutOfRange {
+            value: String,
+            min: i64,
+            max: i64,
+        },
+        NotABoolean(String),
+        NotADate(String),
+        UnknownOption {
+            value: String,
+            options: String,
+        },
+        /// A priority, status or ticket type that isn't one of the names Zendesk knows
+        UnknownName {
+            field: &'static str,
+            value: String,
+            expected: &'static str,
+        },
+        NotATicketId(String),
+        NotAnAssignee(String),
+        UnknownTimezone(String),
+        UnsupportedFieldType(String),
+    }
+
+    impl fmt::Display for ConversionError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let message = match self {
+                ConversionError::Empty => tr!(CellEmpty).to_string(),
+                ConversionError::NotText(value) => tr!(CellNotText, value),
+                ConversionError::NotANumber(value) => tr!(CellNotANumber, value),
+                ConversionError::NotAnInteger(value) => tr!(CellNotAnInteger, value),
+                ConversionError::OutOfRange { value, min, max } => {
+                    tr!(CellOutOfRange, value, min, max)
+                }
+                ConversionError::NotABoolean(value) => tr!(CellNotABoolean, value),
+                ConversionError::NotADate(value) => tr!(CellNotADate, value),
+                ConversionError::UnknownOption { value, options } => {
+                    tr!(CellUnknownOption, value, options)
+                }
+                ConversionError::UnknownName {
+                    value, expected, ..
+                } => tr!(CellUnknownName, value, expected),
+                ConversionError::NotATicketId(value) => tr!(CellNotATicketId, value),
+                ConversionError::NotAnAssignee(value) => tr!(CellNotAnAssignee, value),
+                ConversionError::UnknownTimezone(name) => tr!(CellUnknownTimezone, name),
+                ConversionError::UnsupportedFieldType(name) => {
+                    tr!(CellUnsupportedFieldType, name)
+                }
+            };
+            f.write_str(&message)
+        }
+    }
+
+    impl Serialize for ConversionError {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    /// A cell that could not be converted, with what it held and what the field
+    /// expected, so it can be found and fixed in the sheet
+    #[derive(Serialize, Debug, Clone)]
+    pub struct CellError {
+        pub sheet: String,
+        pub line: usize,
+        /// Address such as "K17"
+        pub cell: String,
+        pub field: String,
+        pub value: String,
+        pub expected: String,
+        pub error: ConversionError,
+    }
+
+    impl fmt::Display for CellError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "{} {} ({}): {}",
+                self.cell, self.field, self.expected, self.error
+            )
+        }
+    }
 
-    /// Toml file. Defaut location on linux is $HOME/.config/zendesk_ticket_importer
-    #[structopt(short, long, name = "CONFIG_FILE")]
-    config_path: Option<PathBuf>,
-}
+    /// Every cell of a row that could not be converted
+    #[derive(Debug)]
+    pub struct RowErrors(pub Vec<CellError>);
 
-// TODO: Create installation script for linux and windows
-#[tokio::main]
-async fn main() -> Result<()> {
-    let mut opt = Opt::from_args();
-    let config = Config::from_opt(&mut opt)?;
-    let importer = Importer::new(&opt.file, config)?;
-    importer.run().await?;
-    Ok(())
+    impl fmt::Display for RowErrors {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let cells: Vec<String> = self.0.iter().map(CellError::to_string).collect();
+            write!(f, "{}", cells.join("; "))
+        }
+    }
 }
 
-pub mod importer {
-    use crate::objects::config::Config;
-    use crate::objects::ticket::Ticket;
+pub mod api {
+    use crate::error::ImportError;
+    use crate::objects::config::{Credentials, HttpSettings};
     use crate::objects::ticket::TicketWrapper;
-    use anyhow::{Context, Result};
     use base64;
-    use calamine::{self, open_workbook_auto, DataType, Range, Reader};
     use chrono::{DateTime, NaiveDate, Utc};
-    use reqwest::{header, Client};
+    use reqwest::{header, Client, RequestBuilder, Response, StatusCode};
+    use serde::de::DeserializeOwned;
     use serde::{Deserialize, Serialize};
-    use std::fmt::Debug;
-    use std::path::Path;
-    use std::time::Duration;
+    use serde_json::Value;
+    use std::fmt;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::{Duration, Instant};
+    use tokio::sync::Mutex;
+    use tokio::time;
 
-    pub struct Importer {
-        range: Range<DataType>,
-        config: Config,
+    type Result<T> = std::result::Result<T, ImportError>;
+
+    const JOB_POLL_INTERVAL: Duration = Duration::from_secs(1);
+    /// How long to back off after a 429 that doesn't say when to retry
+    const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(60);
+    const MAX_RATE_LIMIT_RETRIES: usize = 5;
+    /// Error Zendesk answers with when a bulk request holds more tickets than it takes
+    const TOO_MANY_VALUES: &str = "TooManyValues";
+    /// Most terms sent in one search query, the whole query has to fit in the url
+    pub const SEARCH_TERMS: usize = 50;
+
+    /// Thin wrapper around a `reqwest::Client` that already carries the account's
+    /// credentials and knows which subdomain to talk to.
+    pub struct ZendeskClient {
         client: Client,
+        subdomain: String,
+        budget: RateLimit,
+        /// Requests sent so far, retries included
+        calls: AtomicU64,
+    }
+
+    /// Request budget shared by everything going through the same client, so
+    /// concurrent jobs together stay under the account's rate limit.
+    struct RateLimit {
+        interval: Duration,
+        next_slot: Mutex<Instant>,
+    }
+
+    impl RateLimit {
+        fn new(requests_per_minute: u32) -> Self {
+            let interval = match requests_per_minute {
+                0 => Duration::from_secs(0),
+                rpm => Duration::from_secs(60) / rpm,
+            };
+            RateLimit {
+                interval,
+                next_slot: Mutex::new(Instant::now()),
+            }
+        }
+
+        /// Waits for this request's turn
+        async fn acquire(&self) {
+            let wait = {
+                let mut next_slot = self.next_slot.lock().await;
+                let now = Instant::now();
+                let slot = (*next_slot).max(now);
+                *next_slot = slot + self.interval;
+                slot - now
+            };
+            if wait > Duration::from_secs(0) {
+                time::delay_for(wait).await;
+            }
+        }
+
+        /// Holds every request back until Zendesk accepts them again
+        async fn pause(&self, duration: Duration) {
+            let mut next_slot = self.next_slot.lock().await;
+            *next_slot = (*next_slot).max(Instant::now() + duration);
+        }
     }
 
     #[derive(Debug, Serialize, Deserialize)]
@@ -67,11 +3489,82 @@ pub mod importer {
     pub struct TicketField {
         pub id: usize,
         pub title: String,
+        /// Title before dynamic content placeholders are rendered, e.g. `{{dc.cpf}}`
+        #[serde(default)]
+        pub raw_title: Option<String>,
         #[serde(rename = "type")]
         pub field_type: String,
+        #[serde(default)]
+        pub active: bool,
+        #[serde(default)]
+        pub required: bool,
         pub custom_field_options: Option<Vec<CustomField>>,
     }
 
+    #[derive(Debug, Deserialize)]
+    struct JobStatusResponse {
+        job_status: JobStatus,
+    }
+
+    /// Bulk endpoints answer right away with a queued job, its results have to be
+    /// polled for.
+    #[derive(Clone, Debug, Deserialize)]
+    pub struct JobStatus {
+        pub id: String,
+        pub status: String,
+        #[serde(default)]
+        pub message: Option<String>,
+        #[serde(default)]
+        pub results: Option<Vec<JobResult>>,
+    }
+
+    impl JobStatus {
+        pub fn is_finished(&self) -> bool {
+            matches!(self.status.as_str(), "completed" | "failed" | "killed")
+        }
+    }
+
+    #[derive(Clone, Debug, Deserialize)]
+    pub struct JobResult {
+        #[serde(default)]
+        pub index: Option<usize>,
+        #[serde(default)]
+        pub id: Option<u64>,
+        #[serde(default)]
+        pub success: Option<bool>,
+        #[serde(default)]
+        pub error: Option<String>,
+        #[serde(default)]
+        pub details: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct SearchResponse {
+        results: Vec<Value>,
+        next_page: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct CursorPage {
+        #[serde(default)]
+        meta: CursorMeta,
+        #[serde(default)]
+        links: CursorLinks,
+        #[serde(flatten)]
+        items: serde_json::Map<String, Value>,
+    }
+
+    #[derive(Debug, Default, Deserialize)]
+    struct CursorMeta {
+        #[serde(default)]
+        has_more: bool,
+    }
+
+    #[derive(Debug, Default, Deserialize)]
+    struct CursorLinks {
+        next: Option<String>,
+    }
+
     #[derive(Clone, Debug, Serialize, Deserialize)]
     pub struct CustomField {
         pub name: String,
@@ -86,45 +3579,35 @@ pub mod importer {
         Date(NaiveDate),
         Checkbox(bool),
         MultiSelect(Vec<String>),
+        Null,
     }
 
-    impl Importer {
-        pub fn new<P>(file_path: P, config: Config) -> Result<Self>
-        where
-            P: AsRef<Path> + Debug + Copy,
-        {
-            let range = Importer::get_range(file_path, &config)?;
-            let client = Importer::create_client(&config)?;
-            return Ok(Importer {
-                range,
-                config,
-                client,
-            });
-        }
-
-        fn get_range<P>(file_path: P, config: &Config) -> Result<Range<DataType>>
-        where
-            P: AsRef<Path> + Debug + Copy,
-        {
-            let mut workbook = open_workbook_auto(file_path)
-                .with_context(|| format!("Cannot open file: {:#?}", file_path))?;
-            let s: Range<DataType> = workbook
-                .worksheet_range(&config.worksheet.name)
-                .with_context(|| format!("Could not find worksheet {:#?}", config.worksheet.name))?
-                .unwrap(); // When does this fail? I will keep it as an unwrap for now
-            return Ok(s);
+    impl fmt::Display for ApiValue {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ApiValue::Common(value) => write!(f, "{}", value),
+                ApiValue::DateTime(value) => write!(f, "{}", value.to_rfc3339()),
+                ApiValue::Date(value) => write!(f, "{}", value),
+                ApiValue::Checkbox(value) => write!(f, "{}", value),
+                ApiValue::MultiSelect(values) => write!(f, "{}", values.join(", ")),
+                ApiValue::Null => Ok(()),
+            }
         }
+    }
 
-        fn create_client(config: &Config) -> Result<Client> {
+    impl ZendeskClient {
+        pub fn new(credentials: &Credentials, http: &HttpSettings) -> Result<Self> {
             let authorization = base64::encode(format!(
                 "{}/token:{}",
-                config.credentials.email, config.credentials.api_token
+                credentials.email,
+                credentials.api_token.expose()
             ));
+            let mut auth_header =
+                header::HeaderValue::from_str(format!("Basic {}", authorization).as_str())
+                    .map_err(|_| ImportError::Config(tr!(BadCredentialChars).to_string()))?;
+            auth_header.set_sensitive(true);
             let mut headers = header::HeaderMap::new();
-            headers.insert(
-                header::AUTHORIZATION,
-                header::HeaderValue::from_str(format!("Basic {}", authorization).as_str())?,
-            );
+            headers.insert(header::AUTHORIZATION, auth_header);
             headers.insert(
                 header::CONTENT_TYPE,
                 header::HeaderValue::from_static("application/json"),
@@ -132,77 +3615,216 @@ pub mod importer {
 
             let client = Client::builder()
                 .default_headers(headers)
-                .timeout(Duration::new(10, 0))
+                .timeout(Duration::from_secs(http.timeout))
+                .connect_timeout(Duration::from_secs(http.connect_timeout))
                 .use_rustls_tls()
-                .build()?;
-            return Ok(client);
+                .build()
+                .map_err(ImportError::http(tr!(HttpSetup)))?;
+            Ok(ZendeskClient {
+                client,
+                subdomain: credentials.subdomain.clone(),
+                budget: RateLimit::new(0),
+                calls: AtomicU64::new(0),
+            })
         }
 
-        pub async fn run(self) -> Result<()> {
-            let api_fields = self.get_api_fields().await?;
-            let mut tickets: Vec<Ticket> =
-                Vec::with_capacity(self.range.rows().len() - self.config.worksheet.top_row - 1);
-            for (row_num, row) in self
-                .range
-                .rows()
-                .skip(self.config.worksheet.top_row - 1)
-                .enumerate()
-            {
-                let ticket = Ticket::from_row(row, &self.config, &api_fields);
-                if let Err(err) = ticket {
-                    eprintln!(
-                        "Error processing line {}, cause: {}",
-                        row_num + self.config.worksheet.top_row,
-                        err
-                    );
-                    continue;
-                }
-                tickets.push(ticket.unwrap());
-            }
-            // println!("{:#?}", tickets);
-            for chunk in tickets.chunks(100) {
-                let wrapper = TicketWrapper {
-                    tickets: chunk.to_vec(),
-                };
-                // println!("{}", json!(&wrapper));
+        /// Spaces requests out so no more than `requests_per_minute` are sent, 0 only
+        /// backs off when Zendesk answers with 429
+        pub fn with_rate_limit(mut self, requests_per_minute: u32) -> Self {
+            self.budget = RateLimit::new(requests_per_minute);
+            self
+        }
+
+        pub fn api_calls(&self) -> u64 {
+            self.calls.load(Ordering::Relaxed)
+        }
+
+        fn url(&self, path: &str) -> String {
+            format!("https://{}.zendesk.com{}", self.subdomain, path)
+        }
+
+        /// Sends a request within the rate limit budget, retrying it after the
+        /// `Retry-After` delay when Zendesk answers with 429
+        async fn send(&self, request: RequestBuilder) -> Result<Response> {
+            let request = request.build().map_err(ImportError::http(tr!(HttpBuild)))?;
+            let mut attempts = 0;
+            loop {
+                self.budget.acquire().await;
+                // the Authorization header is never logged
+                debug!(
+                    method = %request.method(),
+                    url = %request.url(),
+                    body_bytes = request.body().and_then(|b| b.as_bytes()).map_or(0, <[u8]>::len),
+                    "Request"
+                );
+                let started = Instant::now();
+                self.calls.fetch_add(1, Ordering::Relaxed);
                 let response = self
                     .client
-                    .post(
-                        format!(
-                            "https://{}.zendesk.com{}",
-                            self.config.credentials.subdomain, self.config.urls.post_many
-                        )
-                        .as_str(),
-                    )
-                    .json(&wrapper)
-                    .send()
+                    .execute(request.try_clone().expect("requests have in-memory bodies"))
                     .await
-                    .with_context(|| "Zendesk server didn't respond")?
-                    .error_for_status()
-                    .with_context(|| "The request for creating tickets failed")?;
-                println!("{:#?}", response.text().await?);
+                    .map_err(ImportError::http(tr!(HttpNoResponse)))?;
+                let header = |name: &str| {
+                    response
+                        .headers()
+                        .get(name)
+                        .and_then(|value| value.to_str().ok())
+                        .unwrap_or("")
+                        .to_string()
+                };
+                debug!(
+                    status = response.status().as_u16(),
+                    elapsed_ms = started.elapsed().as_millis() as u64,
+                    rate_limit_remaining = %header("x-rate-limit-remaining"),
+                    request_id = %header("x-request-id"),
+                    "Response"
+                );
+                if response.status() != StatusCode::TOO_MANY_REQUESTS
+                    || attempts == MAX_RATE_LIMIT_RETRIES
+                {
+                    return Ok(response);
+                }
+                attempts += 1;
+                let retry_after = response
+                    .headers()
+                    .get(header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse().ok())
+                    .map(Duration::from_secs)
+                    .unwrap_or(DEFAULT_RETRY_AFTER);
+                warn!(
+                    retry_after_secs = retry_after.as_secs(),
+                    "Rate limited by Zendesk, waiting"
+                );
+                self.budget.pause(retry_after).await;
             }
-            Ok(())
         }
 
-        pub async fn get_api_fields(&self) -> Result<Vec<TicketField>> {
-            let fields_url = format!(
-                "https://{}.zendesk.com{}",
-                self.config.credentials.subdomain, self.config.urls.get_fields
-            );
-            let fields: GetFieldsReponse = self
-                .client
-                .get(&fields_url)
-                .send()
+        pub async fn get_api_fields(&self, path: &str) -> Result<Vec<TicketField>> {
+            let response = self.send(self.client.get(&self.url(path))).await?;
+            let fields: GetFieldsReponse = Self::read_json(response).await?;
+            Ok(fields.ticket_fields)
+        }
+
+        pub async fn create_many(&self, path: &str, wrapper: &TicketWrapper) -> Result<JobStatus> {
+            let response = self
+                .send(self.client.post(&self.url(path)).json(wrapper))
+                .await?;
+            Self::job_status(response).await
+        }
+
+        /// `tickets` are partial tickets, each one holding its `id` and the fields to change
+        pub async fn update_many(&self, path: &str, tickets: &[Value]) -> Result<JobStatus> {
+            let response = self
+                .send(
+                    self.client
+                        .put(&self.url(path))
+                        .json(&json!({ "tickets": tickets })),
+                )
+                .await?;
+            Self::job_status(response).await
+        }
+
+        /// Reads the job queued by a bulk endpoint, telling apart requests that were
+        /// refused only for being too big: a 413, or the error Zendesk gives for more
+        /// tickets than a bulk request takes
+        async fn job_status(response: Response) -> Result<JobStatus> {
+            let status = response.status();
+            let body = response
+                .text()
                 .await
-                .with_context(|| "Zendesk server didn't respond")?
-                .error_for_status()
-                .with_context(|| "The request for custom url field ids returned an error")?
-                .json()
+                .map_err(ImportError::http(tr!(HttpUnreadable)))?;
+            let error_code = serde_json::from_str::<Value>(&body)
+                .ok()
+                .and_then(|body| body.get("error")?.as_str().map(str::to_string));
+            if status == StatusCode::PAYLOAD_TOO_LARGE
+                || (status.is_client_error() && error_code.as_deref() == Some(TOO_MANY_VALUES))
+            {
+                return Err(ImportError::PayloadTooLarge);
+            }
+            let response: JobStatusResponse = Self::parse_body(status, &body)?;
+            Ok(response.job_status)
+        }
+
+        /// Reads a JSON answer, or the error Zendesk sent instead
+        async fn read_json<T: DeserializeOwned>(response: Response) -> Result<T> {
+            let status = response.status();
+            let body = response
+                .text()
                 .await
-                .with_context(|| "Could not parse response as json")?;
-            // println!("{:#?}", fields);
-            return Ok(fields.ticket_fields);
+                .map_err(ImportError::http(tr!(HttpUnreadable)))?;
+            Self::parse_body(status, &body)
+        }
+
+        fn parse_body<T: DeserializeOwned>(status: StatusCode, body: &str) -> Result<T> {
+            if !status.is_success() {
+                return Err(ImportError::Api {
+                    status: status.as_u16(),
+                    body: body.to_string(),
+                });
+            }
+            serde_json::from_str(body).map_err(|err| ImportError::Api {
+                status: status.as_u16(),
+                body: tr!(UnexpectedAnswer, err, body),
+            })
+        }
+
+        pub async fn wait_for_job(&self, path: &str, mut job: JobStatus) -> Result<JobStatus> {
+            while !job.is_finished() {
+                time::delay_for(JOB_POLL_INTERVAL).await;
+                let response = self
+                    .send(
+                        self.client
+                            .get(&self.url(&format!("{}/{}.json", path, job.id))),
+                    )
+                    .await?;
+                let response: JobStatusResponse = Self::read_json(response).await?;
+                job = response.job_status;
+                debug!(job = %job.id, status = %job.status, "Job status");
+            }
+            Ok(job)
+        }
+
+        /// Follows a cursor paginated endpoint to the end, collecting the array found
+        /// under `key` in every page
+        pub async fn get_all_pages(
+            &self,
+            path: &str,
+            query: &[(&str, &str)],
+            key: &str,
+        ) -> Result<Vec<Value>> {
+            let mut items = Vec::new();
+            let mut request = self
+                .client
+                .get(&self.url(path))
+                .query(query)
+                .query(&[("page[size]", "100")]);
+            loop {
+                let page: CursorPage = Self::read_json(self.send(request).await?).await?;
+                if let Some(Value::Array(page_items)) = page.items.get(key) {
+                    items.extend(page_items.iter().cloned());
+                }
+                match page.links.next {
+                    Some(next) if page.meta.has_more => request = self.client.get(&next),
+                    _ => break,
+                }
+            }
+            Ok(items)
+        }
+
+        /// Runs a search and follows every results page
+        pub async fn search(&self, path: &str, query: &str) -> Result<Vec<Value>> {
+            let mut results = Vec::new();
+            let mut request = self.client.get(&self.url(path)).query(&[("query", query)]);
+            loop {
+                let page: SearchResponse = Self::read_json(self.send(request).await?).await?;
+                results.extend(page.results);
+                match page.next_page {
+                    Some(next_page) => request = self.client.get(&next_page),
+                    None => break,
+                }
+            }
+            Ok(results)
         }
     }
 }
@@ -210,167 +3832,926 @@ pub mod importer {
 pub mod objects {
     pub mod config {
 
+        use crate::error::ImportError;
         use crate::objects::excel_mapper::TicketFields;
         use crate::Opt;
-        use anyhow::{Context, Result};
-        use serde::Deserialize;
-        use std::path::PathBuf;
+        use anyhow::Result;
+        use serde::de::DeserializeOwned;
+        use serde::{Deserialize, Deserializer};
+        use std::collections::HashMap;
+        use std::fmt;
+        use std::path::{Path, PathBuf};
         use std::{env, fs};
 
-        #[derive(Deserialize, Debug)]
+        #[derive(Deserialize, Debug, Clone)]
         pub struct Config {
-            #[serde(rename = "api_url")]
+            #[serde(rename = "api_url", default)]
             pub urls: ApiUrls,
             pub credentials: Credentials,
             pub worksheet: Worksheet,
             pub ticket: TicketFields,
+            #[serde(default)]
+            pub import: ImportSettings,
+            pub dedup: Option<DedupSettings>,
+            #[serde(default)]
+            pub http: HttpSettings,
+            /// `[sheets."name"]` settings that replace the ones above for that sheet only
+            #[serde(default)]
+            pub sheets: HashMap<String, SheetOverrides>,
+        }
+
+        impl Config {
+            /// The config as seen by one worksheet, with its `[sheets."name"]` overrides
+            /// applied
+            pub fn for_sheet(&self, name: &str) -> Config {
+                let mut config = self.clone();
+                config.worksheet.name = vec![name.to_string()];
+                if let Some(overrides) = self.sheets.get(name) {
+                    if let Some(top_row) = overrides.top_row {
+                        config.worksheet.top_row = top_row;
+                    }
+                    if overrides.bottom_row.is_some() {
+                        config.worksheet.bottom_row = overrides.bottom_row;
+                    }
+                    if let Some(ticket) = &overrides.ticket {
+                        config.ticket = ticket.clone();
+                    }
+                }
+                config
+            }
+
+            pub fn from_opt(opt: &mut Opt) -> Result<Self, ImportError> {
+                if opt.config_path.is_none() {
+                    opt.config_path = Some(Config::get_default_path());
+                }
+                let mut config: Config = read_config_file(opt.config_path.as_ref().unwrap())?;
+                config.credentials.apply_overrides(opt)?;
+                config.credentials.check()?;
+                if opt.allow_partial {
+                    config.import.allow_partial = true;
+                }
+                if opt.upsert {
+                    config.import.mode = ImportMode::Upsert;
+                }
+                if opt.summary.is_some() {
+                    config.import.summary = opt.summary.clone();
+                }
+                Ok(config)
+            }
+
+            pub fn get_default_path() -> PathBuf {
+                match env::consts::OS {
+                    "linux" => {
+                        let home = env::var("HOME")
+                            .expect("$HOME variable is not set!")
+                            .clone();
+                        PathBuf::new()
+                            .join(home)
+                            .join(".config/zendesk_ticket_importer/config.toml")
+                    }
+                    "windows" => PathBuf::from("./config.toml"),
+                    other_os => panic!("{} is not supported", other_os),
+                }
+            }
+        }
+
+        fn read_config_file<T: DeserializeOwned>(path: &Path) -> Result<T, ImportError> {
+            let content = fs::read_to_string(path)
+                .map_err(|err| ImportError::Config(tr!(ConfigUnreadable, path.display(), err)))?;
+            toml::from_str(&content)
+                .map_err(|err| ImportError::Config(tr!(ConfigInvalid, path.display(), err)))
+        }
+
+        /// The parts of the config file needed to talk to the API. Commands that don't
+        /// read a workbook use it so they work with a half written config, or none at all
+        /// when the credentials are passed on the command line.
+        #[derive(Deserialize, Debug)]
+        pub struct ApiConfig {
+            #[serde(rename = "api_url", default)]
+            pub urls: ApiUrls,
+            #[serde(default)]
+            pub credentials: Credentials,
+            #[serde(default)]
+            pub http: HttpSettings,
+        }
+
+        impl ApiConfig {
+            pub fn from_opt(opt: &mut Opt) -> Result<Self, ImportError> {
+                if opt.config_path.is_none() {
+                    opt.config_path = Some(Config::get_default_path());
+                }
+                let path = opt.config_path.as_ref().unwrap();
+                let mut config = if path.exists() {
+                    read_config_file(path)?
+                } else {
+                    ApiConfig {
+                        urls: ApiUrls::default(),
+                        credentials: Credentials::default(),
+                        http: HttpSettings::default(),
+                    }
+                };
+                config.credentials.apply_overrides(opt)?;
+                config.credentials.check()?;
+                Ok(config)
+            }
+        }
+
+        #[derive(Deserialize, Debug, Clone)]
+        pub struct ApiUrls {
+            pub get_fields: String,
+            pub post_many: String,
+            #[serde(default = "ApiUrls::default_update_many")]
+            pub update_many: String,
+            #[serde(default = "ApiUrls::default_job_statuses")]
+            pub job_statuses: String,
+            #[serde(default = "ApiUrls::default_search")]
+            pub search: String,
+            #[serde(default = "ApiUrls::default_search_export")]
+            pub search_export: String,
+            #[serde(default = "ApiUrls::default_views")]
+            pub views: String,
+        }
+
+        impl ApiUrls {
+            fn default_update_many() -> String {
+                "/api/v2/tickets/update_many.json".to_string()
+            }
+
+            fn default_job_statuses() -> String {
+                "/api/v2/job_statuses".to_string()
+            }
+
+            fn default_search() -> String {
+                "/api/v2/search.json".to_string()
+            }
+
+            fn default_search_export() -> String {
+                "/api/v2/search/export.json".to_string()
+            }
+
+            fn default_views() -> String {
+                "/api/v2/views".to_string()
+            }
+        }
+
+        impl Default for ApiUrls {
+            fn default() -> Self {
+                ApiUrls {
+                    get_fields: "/api/v2/ticket_fields.json".to_string(),
+                    post_many: "/api/v2/tickets/create_many.json".to_string(),
+                    update_many: ApiUrls::default_update_many(),
+                    job_statuses: ApiUrls::default_job_statuses(),
+                    search: ApiUrls::default_search(),
+                    search_export: ApiUrls::default_search_export(),
+                    views: ApiUrls::default_views(),
+                }
+            }
+        }
+
+        #[derive(Deserialize, Debug, Clone, Default)]
+        pub struct Credentials {
+            /// Where the token is read from, only once it's known that `--api-token`
+            /// doesn't replace it
+            #[serde(default, rename = "api_token")]
+            pub token_source: Option<SecretSource>,
+            #[serde(skip)]
+            pub api_token: Secret,
+            #[serde(default)]
+            pub email: String,
+            #[serde(default)]
+            pub subdomain: String,
+        }
+
+        impl Credentials {
+            fn apply_overrides(&mut self, opt: &Opt) -> Result<(), ImportError> {
+                match (&opt.api_token, self.token_source.take()) {
+                    (Some(api_token), _) => self.api_token = api_token.clone(),
+                    (None, Some(source)) => self.api_token = Secret(source.resolve()?),
+                    (None, None) => {}
+                }
+                if let Some(email) = &opt.email {
+                    self.email = email.clone();
+                }
+                if let Some(subdomain) = &opt.subdomain {
+                    self.subdomain = subdomain.clone();
+                }
+                Ok(())
+            }
+
+            fn check(&self) -> Result<(), ImportError> {
+                let missing = if self.api_token.expose().is_empty() {
+                    tr!(NoApiToken)
+                } else if self.email.is_empty() {
+                    tr!(NoEmail)
+                } else if self.subdomain.is_empty() {
+                    tr!(NoSubdomain)
+                } else {
+                    return Ok(());
+                };
+                Err(ImportError::Config(missing.to_string()))
+            }
+        }
+
+        /// A credential that never shows up in `Debug` output.
+        #[derive(Clone, Default)]
+        pub struct Secret(String);
+
+        impl Secret {
+            pub fn expose(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Debug for Secret {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("Secret(\"***\")")
+            }
+        }
+
+        impl From<&str> for Secret {
+            fn from(value: &str) -> Self {
+                Secret(value.to_string())
+            }
+        }
+
+        /// Where a secret is read from. A bare string is kept for backwards compatibility
+        /// with older config files.
+        #[derive(Deserialize, Clone)]
+        #[serde(untagged)]
+        pub enum SecretSource {
+            Plain(String),
+            Env { env: String },
+            File { file: PathBuf },
+            Keyring { keyring: String, user: String },
+        }
+
+        impl SecretSource {
+            fn resolve(self) -> Result<String, ImportError> {
+                match self {
+                    SecretSource::Plain(value) => Ok(value),
+                    SecretSource::Env { env } => {
+                        env::var(&env).map_err(|_| ImportError::Config(tr!(SecretEnvUnset, env)))
+                    }
+                    SecretSource::File { file } => fs::read_to_string(&file)
+                        .map(|content| content.trim_end().to_string())
+                        .map_err(|err| {
+                            ImportError::Config(tr!(SecretFileUnreadable, file.display(), err))
+                        }),
+                    SecretSource::Keyring { keyring, user } => {
+                        SecretSource::from_keyring(&keyring, &user)
+                    }
+                }
+            }
+
+            #[cfg(feature = "keyring")]
+            fn from_keyring(service: &str, user: &str) -> Result<String, ImportError> {
+                keyring::Entry::new(service, user)
+                    .and_then(|entry| entry.get_password())
+                    .map_err(|err| {
+                        ImportError::Config(tr!(SecretKeyringUnreadable, service, user, err))
+                    })
+            }
+
+            #[cfg(not(feature = "keyring"))]
+            fn from_keyring(_service: &str, _user: &str) -> Result<String, ImportError> {
+                Err(ImportError::Config(tr!(NoKeyringSupport).to_string()))
+            }
+        }
+
+        impl fmt::Debug for SecretSource {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    SecretSource::Plain(_) => f.write_str("Plain(\"***\")"),
+                    SecretSource::Env { env } => f.debug_struct("Env").field("env", env).finish(),
+                    SecretSource::File { file } => {
+                        f.debug_struct("File").field("file", file).finish()
+                    }
+                    SecretSource::Keyring { keyring, user } => f
+                        .debug_struct("Keyring")
+                        .field("keyring", keyring)
+                        .field("user", user)
+                        .finish(),
+                }
+            }
+        }
+
+        #[derive(Deserialize, Debug, Clone)]
+        pub struct Worksheet {
+            /// One sheet name or a list of them
+            #[serde(default, deserialize_with = "one_or_many")]
+            pub name: Vec<String>,
+            /// Regular expression, every sheet whose name matches it is imported too
+            #[serde(default)]
+            pub name_matches: Option<String>,
+            pub top_row: usize,
+            /// Last line to import, everything below it is ignored
+            #[serde(default)]
+            pub bottom_row: Option<usize>,
+            /// End the import at the first blank row instead of skipping over it
+            #[serde(default)]
+            pub stop_at_first_blank: bool,
+            pub timezone: String,
+            /// Separates the decimals of numbers typed in as text, "," for "1.234,56"
+            #[serde(default = "Worksheet::default_decimal_separator")]
+            pub decimal_separator: char,
+            /// Groups the thousands of numbers typed in as text, defaults to "." when
+            /// the decimal separator is "," and to "," otherwise
+            #[serde(default)]
+            pub thousands_separator: Option<char>,
+            /// Text or numbers that check a checkbox field, compared ignoring case
+            #[serde(default = "Worksheet::default_checkbox_true")]
+            pub checkbox_true: Vec<String>,
+            /// Text or numbers that uncheck a checkbox field, compared ignoring case
+            #[serde(default = "Worksheet::default_checkbox_false")]
+            pub checkbox_false: Vec<String>,
+            /// Only rows matching every rule are imported, the rest are skipped
+            #[serde(default)]
+            pub filters: Vec<RowFilter>,
+        }
+
+        impl Worksheet {
+            fn default_decimal_separator() -> char {
+                '.'
+            }
+
+            fn default_checkbox_true() -> Vec<String> {
+                ["sim", "s", "x", "1", "verdadeiro", "yes", "y", "true"]
+                    .iter()
+                    .map(|word| word.to_string())
+                    .collect()
+            }
+
+            fn default_checkbox_false() -> Vec<String> {
+                ["não", "nao", "n", "0", "falso", "no", "false"]
+                    .iter()
+                    .map(|word| word.to_string())
+                    .collect()
+            }
+
+            pub fn thousands(&self) -> char {
+                match (self.thousands_separator, self.decimal_separator) {
+                    (Some(separator), _) => separator,
+                    (None, ',') => '.',
+                    (None, _) => ',',
+                }
+            }
+        }
+
+        /// Settings that differ for one sheet, a `ticket` here replaces the whole
+        /// `[ticket]` mapping
+        #[derive(Deserialize, Debug, Clone)]
+        pub struct SheetOverrides {
+            #[serde(default)]
+            pub top_row: Option<usize>,
+            #[serde(default)]
+            pub bottom_row: Option<usize>,
+            #[serde(default)]
+            pub ticket: Option<TicketFields>,
+        }
+
+        fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            #[derive(Deserialize)]
+            #[serde(untagged)]
+            enum OneOrMany {
+                One(String),
+                Many(Vec<String>),
+            }
+            Ok(match OneOrMany::deserialize(deserializer)? {
+                OneOrMany::One(name) => vec![name],
+                OneOrMany::Many(names) => names,
+            })
+        }
+
+        /// One `[[worksheet.filters]]` rule, e.g. `{ column = "E", equals = "Aberto" }`
+        #[derive(Deserialize, Debug, Clone)]
+        pub struct RowFilter {
+            /// Column the condition looks at, not needed for `rows`
+            #[serde(default)]
+            pub column: Option<String>,
+            #[serde(flatten)]
+            pub condition: FilterCondition,
+        }
+
+        #[derive(Deserialize, Debug, Clone)]
+        #[serde(rename_all = "snake_case")]
+        pub enum FilterCondition {
+            Equals(String),
+            NotEquals(String),
+            OneOf(Vec<String>),
+            /// Regular expression searched for in the cell's text
+            Matches(String),
+            NotEmpty(bool),
+            GreaterThan(FilterValue),
+            LessThan(FilterValue),
+            /// First and last line to import, both included
+            Rows([usize; 2]),
+        }
+
+        /// Numbers compare against numeric cells, anything else is read as a date:
+        /// "2020-12-31", "today" or "today-30"
+        #[derive(Deserialize, Debug, Clone)]
+        #[serde(untagged)]
+        pub enum FilterValue {
+            Number(f64),
+            Date(String),
+        }
+
+        #[derive(Deserialize, Debug, Clone)]
+        pub struct ImportSettings {
+            /// Keep going when the config doesn't match the account's ticket fields
+            #[serde(default)]
+            pub allow_partial: bool,
+            #[serde(default)]
+            pub mode: ImportMode,
+            /// How many batches may be waiting on Zendesk at the same time
+            #[serde(default = "ImportSettings::default_max_in_flight")]
+            pub max_in_flight: usize,
+            /// Budget shared by every request of the run, 0 disables it
+            #[serde(default = "ImportSettings::default_requests_per_minute")]
+            pub requests_per_minute: u32,
+            /// Tickets per bulk request, at most 100. Batches Zendesk finds too large
+            /// are split in half until they go through
+            #[serde(default = "ImportSettings::default_batch_size")]
+            pub batch_size: usize,
+            /// Where to write the JSON summary of each run, "-" prints it to stdout
+            #[serde(default)]
+            pub summary: Option<PathBuf>,
+        }
+
+        impl ImportSettings {
+            fn default_max_in_flight() -> usize {
+                4
+            }
+
+            fn default_batch_size() -> usize {
+                100
+            }
+
+            // lowest limit among Zendesk plans
+            fn default_requests_per_minute() -> u32 {
+                200
+            }
         }
 
-        impl Config {
-            pub fn from_opt(opt: &mut Opt) -> Result<Self> {
-                if !opt.config_path.is_some() {
-                    opt.config_path = Some(Config::get_default_path());
+        impl Default for ImportSettings {
+            fn default() -> Self {
+                ImportSettings {
+                    allow_partial: false,
+                    mode: ImportMode::default(),
+                    max_in_flight: ImportSettings::default_max_in_flight(),
+                    requests_per_minute: ImportSettings::default_requests_per_minute(),
+                    batch_size: ImportSettings::default_batch_size(),
+                    summary: None,
                 }
-                let content: String = fs::read_to_string(opt.config_path.as_ref().unwrap())
-                    .with_context(|| "Could not read config file")?;
-                return Ok(toml::from_str::<Self>(&content)
-                    .with_context(|| "Failed to parse config file as toml")?);
             }
+        }
 
-            fn get_default_path() -> PathBuf {
-                match env::consts::OS {
-                    "linux" => {
-                        let home = env::var("HOME")
-                            .expect("$HOME variable is not set!")
-                            .clone();
-                        return PathBuf::new()
-                            .join(home)
-                            .join(".config/zendesk_ticket_importer/config.toml");
-                    }
-                    "windows" => PathBuf::from("./config.toml"),
-                    other_os => panic!("{} is not supported", other_os),
+        /// Timeouts in seconds for every request sent to Zendesk
+        #[derive(Deserialize, Debug, Clone)]
+        pub struct HttpSettings {
+            #[serde(default = "HttpSettings::default_timeout")]
+            pub timeout: u64,
+            #[serde(default = "HttpSettings::default_connect_timeout")]
+            pub connect_timeout: u64,
+        }
+
+        impl HttpSettings {
+            fn default_timeout() -> u64 {
+                60
+            }
+
+            fn default_connect_timeout() -> u64 {
+                10
+            }
+        }
+
+        impl Default for HttpSettings {
+            fn default() -> Self {
+                HttpSettings {
+                    timeout: HttpSettings::default_timeout(),
+                    connect_timeout: HttpSettings::default_connect_timeout(),
                 }
             }
         }
 
-        #[derive(Deserialize, Debug)]
-        pub struct ApiUrls {
-            pub get_fields: String,
-            pub post_many: String,
+        /// Checks new tickets against each other and against existing tickets before
+        /// creating them
+        #[derive(Deserialize, Debug, Clone)]
+        pub struct DedupSettings {
+            /// System field names or custom field titles/ids whose values together
+            /// identify a ticket
+            pub keys: Vec<String>,
+            #[serde(default)]
+            pub on_duplicate: OnDuplicate,
         }
 
-        #[derive(Deserialize, Debug)]
-        pub struct Credentials {
-            pub api_token: String,
-            pub email: String,
-            pub subdomain: String,
+        #[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+        #[serde(rename_all = "lowercase")]
+        pub enum OnDuplicate {
+            /// Duplicates are left out of the import
+            #[default]
+            Skip,
+            /// Duplicates are imported but listed in the report
+            Flag,
         }
 
-        #[derive(Deserialize, Debug)]
-        pub struct Worksheet {
-            pub name: String,
-            pub top_row: usize,
-            pub timezone: String,
+        #[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+        #[serde(rename_all = "lowercase")]
+        pub enum ImportMode {
+            /// Every row becomes a new ticket
+            #[default]
+            Create,
+            /// Rows whose external_id already exists update that ticket instead
+            Upsert,
         }
     }
 
-    mod excel_mapper {
+    pub mod excel_mapper {
+        use crate::api::TicketField;
+        use calamine::DataType;
+        use chrono::{NaiveDate, NaiveDateTime};
         use serde::de::Error;
         use serde::{Deserialize, Deserializer};
         use std::collections::HashMap;
+        use std::fmt;
+
+        /// Days between Excel's day zero, 1899-12-30, and the unix epoch
+        const EXCEL_EPOCH_OFFSET: f64 = 25569.0;
+
+        /// The moment an Excel date serial stands for, `None` for numbers outside the
+        /// calendar such as a CPF typed into a date column
+        pub fn excel_datetime(serial: f64) -> Option<NaiveDateTime> {
+            let seconds = ((serial - EXCEL_EPOCH_OFFSET) * 86400.0).round() as i64;
+            NaiveDateTime::from_timestamp_opt(seconds, 0)
+        }
+
+        pub fn excel_serial(date: NaiveDate) -> f64 {
+            let days = date.signed_duration_since(NaiveDate::from_ymd(1970, 1, 1));
+            days.num_days() as f64 + EXCEL_EPOCH_OFFSET
+        }
+
+        pub fn index_to_excel_column(mut index: usize) -> String {
+            let mut letters = Vec::new();
+            loop {
+                letters.push((b'A' + (index % 26) as u8) as char);
+                if index < 26 {
+                    break;
+                }
+                index = index / 26 - 1;
+            }
+            letters.iter().rev().collect()
+        }
 
-        fn excel_column_to_index(col_name: &str) -> Option<usize> {
-            if col_name.is_empty() {
+        /// `None` unless `col_name` is one to three letters, the most an Excel column takes
+        pub fn excel_column_to_index(col_name: &str) -> Option<usize> {
+            if col_name.is_empty()
+                || col_name.len() > 3
+                || !col_name.chars().all(|c| c.is_ascii_alphabetic())
+            {
                 return None;
             }
             let mut ans = 0;
-            col_name.to_owned().make_ascii_uppercase();
-            for (idx, letter) in col_name.chars().rev().enumerate() {
+            for (idx, letter) in col_name.to_ascii_uppercase().chars().rev().enumerate() {
                 let num = (letter as u32) - 64;
                 ans += num * 26u32.pow(idx as u32);
             }
-            return Some((ans - 1) as usize);
+            Some((ans - 1) as usize)
         }
 
-        #[derive(Deserialize, Debug)]
+        #[derive(Deserialize, Debug, Clone)]
         pub struct TicketFields {
             pub system_fields: SystemFields,
             #[serde(deserialize_with = "custom_flattener")]
-            pub custom_fields: HashMap<String, usize>,
+            pub custom_fields: Vec<CustomFieldMapping>,
         }
 
-        #[derive(Deserialize, Debug)]
+        #[derive(Deserialize, Debug, Clone)]
         pub struct SystemFields {
-            #[serde(deserialize_with = "custom_deserializer")]
-            pub comment: usize,
-            #[serde(deserialize_with = "custom_deserializer_opt")]
-            pub subject: Option<usize>,
-            #[serde(deserialize_with = "custom_deserializer_opt")]
-            pub status: Option<usize>,
-            #[serde(deserialize_with = "custom_deserializer_opt")]
-            pub tickettype: Option<usize>,
-            #[serde(deserialize_with = "custom_deserializer_opt")]
-            pub assignee: Option<usize>,
-            #[serde(deserialize_with = "custom_deserializer_opt")]
-            pub priority: Option<usize>,
-        }
-
-        fn custom_flattener<'de, D>(deserializer: D) -> Result<HashMap<String, usize>, D::Error>
+            #[serde(deserialize_with = "source_deserializer")]
+            pub comment: FieldSource,
+            #[serde(deserialize_with = "source_deserializer_opt")]
+            pub subject: Option<FieldSource>,
+            #[serde(deserialize_with = "source_deserializer_opt")]
+            pub status: Option<FieldSource>,
+            #[serde(deserialize_with = "source_deserializer_opt")]
+            pub tickettype: Option<FieldSource>,
+            #[serde(deserialize_with = "source_deserializer_opt")]
+            pub assignee: Option<FieldSource>,
+            #[serde(deserialize_with = "source_deserializer_opt")]
+            pub priority: Option<FieldSource>,
+            #[serde(default, deserialize_with = "source_deserializer_opt")]
+            pub external_id: Option<FieldSource>,
+            /// Zendesk ticket id, only read by the update subcommand
+            #[serde(default, deserialize_with = "source_deserializer_opt")]
+            pub id: Option<FieldSource>,
+        }
+
+        impl SystemFields {
+            /// Every mapped system field along with where its value comes from
+            pub fn sources(&self) -> Vec<(&'static str, &FieldSource)> {
+                let optional = [
+                    ("subject", &self.subject),
+                    ("status", &self.status),
+                    ("tickettype", &self.tickettype),
+                    ("assignee", &self.assignee),
+                    ("priority", &self.priority),
+                    ("external_id", &self.external_id),
+                    ("id", &self.id),
+                ];
+                std::iter::once(("comment", &self.comment))
+                    .chain(
+                        optional
+                            .iter()
+                            .filter_map(|(name, source)| source.as_ref().map(|s| (*name, s))),
+                    )
+                    .collect()
+            }
+
+            /// Every system field read from a column along with that column
+            pub fn columns(&self) -> Vec<(&'static str, usize)> {
+                self.sources()
+                    .into_iter()
+                    .filter_map(|(name, source)| source.column.map(|c| (name, c)))
+                    .collect()
+            }
+        }
+
+        /// Where a mapped field takes its value from: a column, a column with a
+        /// `default` for its empty cells, or a literal `value` that every row gets.
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct FieldSource {
+            pub column: Option<usize>,
+            pub default: Option<DataType>,
+        }
+
+        impl FieldSource {
+            pub fn column(column: usize) -> Self {
+                FieldSource {
+                    column: Some(column),
+                    default: None,
+                }
+            }
+
+            /// The row's cell, or the default when that cell is empty or blank
+            pub fn cell<'a>(&'a self, row: &'a [DataType]) -> Option<&'a DataType> {
+                let cell = self.column.and_then(|column| row.get(column));
+                match cell {
+                    Some(DataType::Empty) | None => self.default.as_ref().or(cell),
+                    Some(DataType::String(s)) if s.trim().is_empty() => {
+                        self.default.as_ref().or(cell)
+                    }
+                    _ => cell,
+                }
+            }
+        }
+
+        /// A `value` or `default` as written in the config. Dates become Excel serial
+        /// numbers so they convert like a date cell would.
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Literal {
+            Bool(bool),
+            Int(i64),
+            Float(f64),
+            Date(toml::value::Datetime),
+            Text(String),
+        }
+
+        impl Literal {
+            fn into_cell<E: Error>(self) -> Result<DataType, E> {
+                Ok(match self {
+                    Literal::Bool(b) => DataType::Bool(b),
+                    Literal::Int(i) => DataType::Int(i),
+                    Literal::Float(f) => DataType::Float(f),
+                    Literal::Text(s) => DataType::String(s),
+                    Literal::Date(datetime) => {
+                        let text = datetime.to_string();
+                        let date = text
+                            .get(..10)
+                            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+                            .ok_or_else(|| E::custom(tr!(LiteralNoDate, text)))?;
+                        DataType::Float(excel_serial(date))
+                    }
+                })
+            }
+        }
+
+        /// Accepted forms are `"H"`, `{ column = "H", default = ... }` and
+        /// `{ value = ... }`.
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawSource {
+            Column(String),
+            Table {
+                column: Option<String>,
+                value: Option<Literal>,
+                default: Option<Literal>,
+            },
+        }
+
+        /// `None` when the column is left blank, which unmaps the field
+        fn to_source<E: Error>(
+            column: Option<String>,
+            value: Option<Literal>,
+            default: Option<Literal>,
+        ) -> Result<Option<FieldSource>, E> {
+            match (column, value) {
+                (Some(_), Some(_)) => Err(E::custom(tr!(ColumnAndValue))),
+                (None, Some(_)) if default.is_some() => Err(E::custom(tr!(ValueWithDefault))),
+                (None, Some(value)) => Ok(Some(FieldSource {
+                    column: None,
+                    default: Some(value.into_cell()?),
+                })),
+                (None, None) => Err(E::custom(tr!(ColumnOrValueRequired))),
+                (Some(column), None) if column.is_empty() => Ok(None),
+                (Some(column), None) => {
+                    let column = excel_column_to_index(&column)
+                        .ok_or_else(|| E::custom(tr!(ColumnNotLetters)))?;
+                    Ok(Some(FieldSource {
+                        column: Some(column),
+                        default: default.map(Literal::into_cell).transpose()?,
+                    }))
+                }
+            }
+        }
+
+        /// How a `[ticket.custom_fields]` entry points at a Zendesk field. Ids survive
+        /// renames in the admin UI, titles are easier to read.
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub enum FieldRef {
+            Id(usize),
+            Title(String),
+        }
+
+        impl FieldRef {
+            pub fn matches(&self, field: &TicketField) -> bool {
+                match self {
+                    FieldRef::Id(id) => field.id == *id,
+                    FieldRef::Title(title) => {
+                        field.title == *title || field.raw_title.as_ref() == Some(title)
+                    }
+                }
+            }
+
+            pub fn find<'a>(&self, api_fields: &'a [TicketField]) -> Option<&'a TicketField> {
+                api_fields.iter().find(|field| self.matches(field))
+            }
+        }
+
+        impl fmt::Display for FieldRef {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    FieldRef::Id(id) => write!(f, "id {}", id),
+                    FieldRef::Title(title) => write!(f, "{:?}", title),
+                }
+            }
+        }
+
+        #[derive(Debug, Clone)]
+        pub struct CustomFieldMapping {
+            pub field: FieldRef,
+            pub source: FieldSource,
+            /// Unset means `required` for the fields Zendesk requires and
+            /// `skip_if_empty` for the others
+            pub policy: Option<EmptyPolicy>,
+        }
+
+        /// What becomes of a custom field whose cell is empty
+        #[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+        #[serde(rename_all = "snake_case")]
+        pub enum EmptyPolicy {
+            /// The row is invalid
+            Required,
+            /// The field is left out of the ticket
+            SkipIfEmpty,
+            /// The field is sent empty, which clears it when updating
+            NullIfEmpty,
+        }
+
+        /// Accepted forms are `Title = "H"`, `"360001234" = "H"` and
+        /// `Anything = { id = 360001234, column = "H" }`, where the table also takes
+        /// a `default` or a literal `value` instead of the column, and the `policy` for
+        /// empty cells.
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawCustomField {
+            Column(String),
+            Table {
+                id: Option<usize>,
+                column: Option<String>,
+                value: Option<Literal>,
+                default: Option<Literal>,
+                policy: Option<EmptyPolicy>,
+            },
+        }
+
+        fn custom_flattener<'de, D>(deserializer: D) -> Result<Vec<CustomFieldMapping>, D::Error>
         where
             D: Deserializer<'de>,
         {
-            let map: HashMap<&str, &str> = Deserialize::deserialize(deserializer)?;
-            let mut new_map: HashMap<String, usize> = HashMap::with_capacity(map.capacity());
+            let map: HashMap<String, RawCustomField> = Deserialize::deserialize(deserializer)?;
+            let mut mappings: Vec<CustomFieldMapping> = Vec::with_capacity(map.len());
             for (k, v) in map.into_iter() {
-                if v.is_empty() {
-                    continue;
-                }
-                if !v.chars().all(|c| char::is_ascii_alphabetic(&c)) {
-                    return Err(D::Error::custom(
-                        "Excel columns can't contain non-ascii-aphabetic characters",
-                    ));
-                }
-                new_map.insert(k.to_string(), excel_column_to_index(v).unwrap());
+                let (id, source, policy) = match v {
+                    RawCustomField::Column(column) => {
+                        (None, to_source(Some(column), None, None)?, None)
+                    }
+                    RawCustomField::Table {
+                        id,
+                        column,
+                        value,
+                        default,
+                        policy,
+                    } => (id, to_source(column, value, default)?, policy),
+                };
+                let source = match source {
+                    Some(source) => source,
+                    None => continue,
+                };
+                let field = match id {
+                    Some(id) => FieldRef::Id(id),
+                    None if !k.is_empty() && k.chars().all(|c| c.is_ascii_digit()) => {
+                        FieldRef::Id(k.parse().map_err(D::Error::custom)?)
+                    }
+                    None => FieldRef::Title(k),
+                };
+                mappings.push(CustomFieldMapping {
+                    field,
+                    source,
+                    policy,
+                });
             }
-            Ok(new_map)
+            mappings.sort_by_key(|m| m.source.column);
+            Ok(mappings)
         }
-        fn custom_deserializer<'de, D>(deserializer: D) -> Result<usize, D::Error>
+
+        fn raw_source<'de, D>(deserializer: D) -> Result<Option<FieldSource>, D::Error>
         where
             D: Deserializer<'de>,
         {
-            let s: &str = Deserialize::deserialize(deserializer)?;
-
-            if !s.chars().all(|c| char::is_ascii_alphabetic(&c)) {
-                return Err(D::Error::custom(
-                    "Excel columns can't contain non-ascii-aphabetic characters",
-                ));
+            match Deserialize::deserialize(deserializer)? {
+                RawSource::Column(column) => to_source(Some(column), None, None),
+                RawSource::Table {
+                    column,
+                    value,
+                    default,
+                } => to_source(column, value, default),
             }
-            Ok(excel_column_to_index(s).unwrap())
         }
 
-        fn custom_deserializer_opt<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+        fn source_deserializer<'de, D>(deserializer: D) -> Result<FieldSource, D::Error>
         where
             D: Deserializer<'de>,
         {
-            let s: &str = Deserialize::deserialize(deserializer)?;
+            raw_source(deserializer)?.ok_or_else(|| D::Error::custom(tr!(ColumnRequired)))
+        }
 
-            if !s.chars().all(|c| char::is_ascii_alphabetic(&c)) {
-                return Err(D::Error::custom(
-                    "Excel columns can't contain non-ascii-aphabetic characters",
-                ));
+        fn source_deserializer_opt<'de, D>(deserializer: D) -> Result<Option<FieldSource>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            raw_source(deserializer)
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            #[test]
+            fn column_letters_map_to_indexes() {
+                assert_eq!(excel_column_to_index("A"), Some(0));
+                assert_eq!(excel_column_to_index("z"), Some(25));
+                assert_eq!(excel_column_to_index("AA"), Some(26));
+                assert_eq!(excel_column_to_index("XFD"), Some(16383));
+                assert_eq!(index_to_excel_column(16383), "XFD");
+            }
+
+            #[test]
+            fn column_names_are_one_to_three_letters() {
+                assert_eq!(excel_column_to_index(""), None);
+                assert_eq!(excel_column_to_index("AAAA"), None);
+                assert_eq!(excel_column_to_index("A1"), None);
+                assert_eq!(excel_column_to_index("1"), None);
             }
-            Ok(excel_column_to_index(s))
         }
     }
 
     pub mod ticket {
-        use crate::importer::{ApiValue, TicketField};
-        use crate::objects::config::Config;
-        use anyhow::Result;
+        use crate::api::{ApiValue, TicketField};
+        use crate::error::{CellError, ConversionError, ImportError, RowErrors};
+        use crate::objects::config::{Config, Worksheet};
+        use crate::objects::excel_mapper::{
+            excel_datetime, index_to_excel_column, EmptyPolicy, FieldSource,
+        };
         use calamine::DataType;
         use chrono::Utc;
-        use chrono::{NaiveDateTime, TimeZone};
+        use chrono::{Duration, TimeZone};
         use serde::Serialize;
+        use std::ops::RangeInclusive;
+        use std::str::FromStr;
 
         #[derive(Serialize, Debug)]
         pub struct TicketWrapper {
@@ -379,97 +4760,368 @@ pub mod objects {
 
         #[derive(Serialize, Debug, Clone)]
         pub struct Ticket {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            id: Option<u64>,
             #[serde(skip_serializing_if = "Option::is_none")]
             subject: Option<String>,
-            comment: Comment,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            comment: Option<Comment>,
             #[serde(skip_serializing_if = "Option::is_none")]
             priority: Option<Priority>,
             #[serde(skip_serializing_if = "Option::is_none")]
             status: Option<Status>,
             #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
             tickettype: Option<TicketType>,
+            #[serde(flatten)]
+            assignee: Option<Assignee>,
             #[serde(skip_serializing_if = "Option::is_none")]
-            assignee: Option<String>,
-            custom_fields: Vec<Option<CustomFields>>,
+            external_id: Option<String>,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            custom_fields: Vec<CustomFields>,
         }
 
         impl Ticket {
+            /// `line` is the row's 1-based line number, used to point at the cells that
+            /// could not be converted. Those come back together as `RowErrors`.
             pub fn from_row(
                 row: &[DataType],
+                line: usize,
+                config: &Config,
+                api_fields: &[TicketField],
+            ) -> Result<Self, ImportError> {
+                let mut check = RowCheck::new(row, line, config);
+                let ticket = Ticket::build(&mut check, config, api_fields, false);
+                check.finish(ticket)
+            }
+
+            /// Builds a partial ticket for `update_many`: the ticket id comes from the id
+            /// column, and empty cells leave the matching field untouched instead of
+            /// failing the row.
+            pub fn update_from_row(
+                row: &[DataType],
+                line: usize,
+                config: &Config,
+                api_fields: &[TicketField],
+            ) -> Result<Self, ImportError> {
+                let id_source = config
+                    .ticket
+                    .system_fields
+                    .id
+                    .as_ref()
+                    .ok_or_else(|| ImportError::Config(tr!(NoIdColumn).to_string()))?;
+                let mut check = RowCheck::new(row, line, config);
+                let id = check.convert(id_source, "id", tr!(ExpectedTicketId), |cell| {
+                    let id = cell
+                        .and_then(cell_to_string)
+                        .ok_or(ConversionError::Empty)?;
+                    id.trim_start_matches('#')
+                        .parse::<u64>()
+                        .map_err(|_| ConversionError::NotATicketId(id.clone()))
+                });
+                let mut ticket = Ticket::build(&mut check, config, api_fields, true);
+                ticket.id = id;
+                check.finish(ticket)
+            }
+
+            fn build(
+                check: &mut RowCheck,
                 config: &Config,
-                api_fields: &Vec<TicketField>,
-            ) -> Result<Self> {
+                api_fields: &[TicketField],
+                partial: bool,
+            ) -> Self {
+                let row = check.row;
                 let t = &config.ticket.system_fields;
                 let subject = t
                     .subject
-                    .and_then(|x| row.get(x))
+                    .as_ref()
+                    .and_then(|source| source.cell(row))
                     .and_then(DataType::get_string)
                     .map(str::to_string);
-                let comment = Comment {
-                    body: row
-                        .get(t.comment)
-                        .and_then(DataType::get_string)
-                        .ok_or_else(|| anyhow!("Comment cell should be a string"))?
-                        .to_string(),
+                let comment = match t.comment.cell(row) {
+                    cell if partial && is_blank(cell) => None,
+                    _ => check.convert(&t.comment, "comment", tr!(ExpectedText), |cell| {
+                        Ok(Comment {
+                            body: text(cell)?.to_string(),
+                        })
+                    }),
                 };
-                let priority = t
-                    .priority
-                    .and_then(|x| row.get(x))
-                    .and_then(DataType::get_string)
-                    .map(Priority::from_str)
-                    .transpose()?;
-
-                let status = t
-                    .status
-                    .and_then(|x| row.get(x))
-                    .and_then(DataType::get_string)
-                    .map(Status::from_str)
-                    .transpose()?;
-                let tickettype = t
-                    .tickettype
-                    .and_then(|x| row.get(x))
-                    .and_then(DataType::get_string)
-                    .map(TicketType::from_str)
-                    .transpose()?;
-                let assignee = t
-                    .assignee
-                    .and_then(|x| row.get(x))
-                    .and_then(DataType::get_string)
-                    .map(str::to_string);
+                let priority = t.priority.as_ref().and_then(|source| {
+                    check.convert_text(
+                        source,
+                        "priority",
+                        tr!(ExpectedPriority),
+                        Priority::from_str,
+                    )
+                });
+                let status = t.status.as_ref().and_then(|source| {
+                    check.convert_text(source, "status", tr!(ExpectedStatus), Status::from_str)
+                });
+                let tickettype = t.tickettype.as_ref().and_then(|source| {
+                    check.convert_text(
+                        source,
+                        "tickettype",
+                        tr!(ExpectedTicketType),
+                        TicketType::from_str,
+                    )
+                });
+                let assignee = t.assignee.as_ref().and_then(|source| {
+                    let text = source.cell(row).and_then(cell_to_string)?;
+                    check.convert(source, "assignee", tr!(ExpectedAssignee), |_| {
+                        Assignee::from_str(&text)
+                    })
+                });
+                let external_id = t
+                    .external_id
+                    .as_ref()
+                    .and_then(|source| source.cell(row))
+                    .and_then(cell_to_string);
                 let mut custom_fields = Vec::with_capacity(config.ticket.custom_fields.len());
-                for (key, value) in config.ticket.custom_fields.iter() {
-                    let custom_field = api_fields
-                        .iter()
-                        .find(|&x| x.title == *key)
-                        .map(|field| {
-                            let data = row.get(*value);
-                            match field.field_type.as_str() {
-                                "integer" => CustomFields::from_integer(data, field),
-                                "decimal" => CustomFields::from_decimal(data, field),
-                                "date" => {
-                                    CustomFields::from_date(data, field, &config.worksheet.timezone)
-                                }
-                                "checkbox" => CustomFields::from_checkbox(data, field),
-                                "text" => CustomFields::from_text(data, field),
-                                "tagger" => CustomFields::from_tagger(data, field),
-                                unknown => {
-                                    return Err(anyhow!("Unknown Zendesk field type: {}", unknown))
-                                }
+                for mapping in config.ticket.custom_fields.iter() {
+                    let field = match mapping.field.find(api_fields) {
+                        Some(field) => field,
+                        None => {
+                            trace!(field = %mapping.field, "Left out, not a field of the account");
+                            continue;
+                        }
+                    };
+                    let expected = expected(field);
+                    let blank = is_blank(mapping.source.cell(row));
+                    // optional checkboxes are left unchecked on new tickets
+                    let optional_checkbox = field.field_type == "checkbox" && !field.required;
+                    if blank && optional_checkbox && !partial && mapping.policy.is_none() {
+                        custom_fields.push(CustomFields::unchecked(field));
+                        continue;
+                    }
+                    if blank {
+                        // updates leave the field as it is unless told otherwise
+                        let policy = mapping.policy.unwrap_or(if field.required && !partial {
+                            EmptyPolicy::Required
+                        } else {
+                            EmptyPolicy::SkipIfEmpty
+                        });
+                        match policy {
+                            EmptyPolicy::Required => {
+                                check.convert(&mapping.source, &field.title, expected, |_| {
+                                    Err::<(), _>(ConversionError::Empty)
+                                });
                             }
-                        })
-                        .transpose()?;
-                    custom_fields.push(custom_field);
+                            EmptyPolicy::SkipIfEmpty => {
+                                trace!(field = %mapping.field, "Left out, the cell is empty");
+                            }
+                            EmptyPolicy::NullIfEmpty => {
+                                trace!(field = %mapping.field, "Cleared, the cell is empty");
+                                custom_fields.push(CustomFields::null(field));
+                            }
+                        }
+                        continue;
+                    }
+                    trace!(
+                        field = %mapping.field,
+                        column = ?mapping.source.column.map(index_to_excel_column),
+                        field_type = %field.field_type,
+                        cell = ?mapping.source.cell(row),
+                        "Converting custom field"
+                    );
+                    let custom_field =
+                        check.convert(&mapping.source, &field.title, expected, |data| {
+                            CustomFields::convert(data, field, &config.worksheet)
+                        });
+                    custom_fields.extend(custom_field);
                 }
 
-                Ok(Ticket {
+                Ticket {
+                    id: None,
                     subject,
                     comment,
                     priority,
                     status,
                     tickettype,
                     assignee,
+                    external_id,
                     custom_fields,
-                })
+                }
+            }
+
+            pub fn external_id(&self) -> Option<&str> {
+                self.external_id.as_deref()
+            }
+        }
+
+        /// Converts the `{ value = ... }` entries of the mapping the way their cells would
+        /// be, so a literal that can never be sent is caught once instead of on every row
+        pub fn literal_errors(config: &Config, api_fields: &[TicketField]) -> Vec<CellError> {
+            let mut check = RowCheck::new(&[], 0, config);
+            let is_literal = |source: &&FieldSource| source.column.is_none();
+            let t = &config.ticket.system_fields;
+            if let Some(source) = t.subject.as_ref().filter(is_literal) {
+                check.convert(source, "subject", tr!(ExpectedText), |cell| {
+                    text(cell).map(drop)
+                });
+            }
+            if t.comment.column.is_none() {
+                check.convert(&t.comment, "comment", tr!(ExpectedText), |cell| {
+                    text(cell).map(drop)
+                });
+            }
+            if let Some(source) = t.priority.as_ref().filter(is_literal) {
+                check.convert(source, "priority", tr!(ExpectedPriority), |cell| {
+                    Priority::from_str(text(cell)?)
+                });
+            }
+            if let Some(source) = t.status.as_ref().filter(is_literal) {
+                check.convert(source, "status", tr!(ExpectedStatus), |cell| {
+                    Status::from_str(text(cell)?)
+                });
+            }
+            if let Some(source) = t.tickettype.as_ref().filter(is_literal) {
+                check.convert(source, "tickettype", tr!(ExpectedTicketType), |cell| {
+                    TicketType::from_str(text(cell)?)
+                });
+            }
+            if let Some(source) = t.assignee.as_ref().filter(is_literal) {
+                check.convert(source, "assignee", tr!(ExpectedAssignee), |cell| {
+                    let text = cell
+                        .and_then(cell_to_string)
+                        .ok_or(ConversionError::Empty)?;
+                    Assignee::from_str(&text)
+                });
+            }
+            let literals = config
+                .ticket
+                .custom_fields
+                .iter()
+                .filter(|mapping| mapping.source.column.is_none());
+            for mapping in literals {
+                // unknown fields are reported by the validation on their own
+                if let Some(field) = mapping.field.find(api_fields) {
+                    check.convert(&mapping.source, &field.title, expected(field), |data| {
+                        CustomFields::convert(data, field, &config.worksheet)
+                    });
+                }
+            }
+            check.errors
+        }
+
+        /// What a custom field takes, named for the people fixing the sheet
+        fn expected(field: &TicketField) -> &str {
+            match field.field_type.as_str() {
+                "integer" => tr!(ExpectedInteger),
+                "decimal" => tr!(ExpectedDecimal),
+                "date" => tr!(ExpectedDate),
+                "checkbox" => tr!(ExpectedCheckbox),
+                "text" => tr!(ExpectedText),
+                "tagger" => tr!(ExpectedOption),
+                other => other,
+            }
+        }
+
+        /// Converts the cells of one row, setting aside the ones that fail instead of
+        /// stopping at the first
+        struct RowCheck<'a> {
+            row: &'a [DataType],
+            line: usize,
+            sheet: &'a str,
+            errors: Vec<CellError>,
+        }
+
+        impl<'a> RowCheck<'a> {
+            fn new(row: &'a [DataType], line: usize, config: &'a Config) -> Self {
+                RowCheck {
+                    row,
+                    line,
+                    sheet: config.worksheet.name.first().map_or("", String::as_str),
+                    errors: Vec::new(),
+                }
+            }
+
+            /// Literal values have no cell to point at and are reported as such
+            fn convert<T>(
+                &mut self,
+                source: &FieldSource,
+                field: &str,
+                expected: &str,
+                convert: impl FnOnce(Option<&DataType>) -> Result<T, ConversionError>,
+            ) -> Option<T> {
+                let cell = source.cell(self.row);
+                match convert(cell) {
+                    Ok(value) => Some(value),
+                    Err(err) => {
+                        self.errors.push(CellError {
+                            sheet: self.sheet.to_string(),
+                            line: self.line,
+                            cell: match source.column {
+                                Some(column) => {
+                                    format!("{}{}", index_to_excel_column(column), self.line)
+                                }
+                                None => tr!(LiteralCell).to_string(),
+                            },
+                            field: field.to_string(),
+                            value: cell.map(DataType::to_string).unwrap_or_default(),
+                            expected: expected.to_string(),
+                            error: err,
+                        });
+                        None
+                    }
+                }
+            }
+
+            /// Like `convert` for the system fields that take a name, which are left
+            /// out when the cell holds no text
+            fn convert_text<T>(
+                &mut self,
+                source: &FieldSource,
+                field: &str,
+                expected: &str,
+                parse: impl FnOnce(&str) -> Result<T, ConversionError>,
+            ) -> Option<T> {
+                let text = source
+                    .cell(self.row)
+                    .and_then(DataType::get_string)
+                    .filter(|text| !text.trim().is_empty())?;
+                self.convert(source, field, expected, |_| parse(text))
+            }
+
+            fn finish(self, ticket: Ticket) -> Result<Ticket, ImportError> {
+                if !self.errors.is_empty() {
+                    return Err(ImportError::InvalidRow(RowErrors(self.errors)));
+                }
+                trace!(ticket = ?ticket, "Row converted");
+                Ok(ticket)
+            }
+        }
+
+        /// Ids are often typed in as numbers, which calamine hands back as floats
+        pub fn cell_to_string(cell: &DataType) -> Option<String> {
+            match cell {
+                DataType::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+                DataType::Float(f) if f.fract() == 0.0 => Some(format!("{}", *f as i64)),
+                DataType::Float(f) => Some(f.to_string()),
+                DataType::Int(i) => Some(i.to_string()),
+                _ => None,
+            }
+        }
+
+        /// Zendesk takes the agent's user id or their email, there is no `assignee` key
+        #[derive(Serialize, Debug, Clone)]
+        enum Assignee {
+            #[serde(rename = "assignee_id")]
+            Id(u64),
+            #[serde(rename = "assignee_email")]
+            Email(String),
+        }
+
+        impl FromStr for Assignee {
+            type Err = ConversionError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let s = s.trim();
+                if let Ok(id) = s.trim_start_matches('#').parse() {
+                    Ok(Assignee::Id(id))
+                } else if s.contains('@') {
+                    Ok(Assignee::Email(s.to_string()))
+                } else {
+                    Err(ConversionError::NotAnAssignee(s.to_string()))
+                }
             }
         }
 
@@ -490,17 +5142,20 @@ pub mod objects {
             Urgent,
         }
 
-        impl Priority {
-            pub fn from_str(name: &str) -> Result<Self> {
-                println!("convertendo priority");
+        impl FromStr for Priority {
+            type Err = ConversionError;
+
+            fn from_str(name: &str) -> Result<Self, ConversionError> {
                 match name.to_ascii_lowercase().as_str() {
                     "low" | "baixa" => Ok(Priority::Low),
                     "normal" => Ok(Priority::Normal),
                     "high" | "alta" => Ok(Priority::High),
                     "urgent" | "urgente" => Ok(Priority::Urgent),
-                    _ => Err(anyhow!(
-                        "Unknown priority, expected low/baixa, normal, high/alta, urgente/urgente"
-                    )),
+                    _ => Err(ConversionError::UnknownName {
+                        field: "priority",
+                        value: name.to_string(),
+                        expected: "low/baixa, normal, high/alta, urgent/urgente",
+                    }),
                 }
             }
         }
@@ -521,15 +5176,22 @@ pub mod objects {
             Closed,
         }
 
-        impl Status {
-            pub fn from_str(name: &str) -> Result<Self> {
+        impl FromStr for Status {
+            type Err = ConversionError;
+
+            fn from_str(name: &str) -> Result<Self, ConversionError> {
                 match name.to_ascii_lowercase().as_str() {
+                    "new" | "novo" => Ok(Status::New),
                     "open" | "aberto" => Ok(Status::Open),
                     "pending" | "pendente" => Ok(Status::Pending),
                     "hold" | "em espera" => Ok(Status::Hold),
                     "solved" | "resolvido" => Ok(Status::Solved),
                     "closed" | "fechado" => Ok(Status::Closed),
-                    _ => Err(anyhow!("Unknown status, expected open/aberto, pending/pendente, hold/em espera, solved/resolvido, closed/fechado")),
+                    _ => Err(ConversionError::UnknownName {
+                        field: "status",
+                        value: name.to_string(),
+                        expected: "new/novo, open/aberto, pending/pendente, hold/em espera, solved/resolvido, closed/fechado",
+                    }),
                 }
             }
         }
@@ -546,14 +5208,21 @@ pub mod objects {
             Task,
         }
 
-        impl TicketType {
-            pub fn from_str(name: &str) -> Result<Self> {
+        impl FromStr for TicketType {
+            type Err = ConversionError;
+
+            fn from_str(name: &str) -> Result<Self, ConversionError> {
                 match name.to_ascii_lowercase().as_str() {
                     "question" | "pergunta" => Ok(TicketType::Question),
                     "incident" | "incidente" => Ok(TicketType::Incident),
                     "problem" | "problema" => Ok(TicketType::Problem),
                     "task" | "tarefa" => Ok(TicketType::Task),
-                    _ => Err(anyhow!("Unknown ticket type, expected question/pergunta, incident/incidente, problem/problema, task/tarefa")),
+                    _ => Err(ConversionError::UnknownName {
+                        field: "ticket type",
+                        value: name.to_string(),
+                        expected:
+                            "question/pergunta, incident/incidente, problem/problema, task/tarefa",
+                    }),
                 }
             }
         }
@@ -565,100 +5234,305 @@ pub mod objects {
         }
 
         impl CustomFields {
+            /// Empty cells of optional checkboxes leave them unchecked
+            pub fn unchecked(api_field: &TicketField) -> Self {
+                Self {
+                    id: api_field.id,
+                    value: ApiValue::Checkbox(false),
+                }
+            }
+
+            /// Picks the conversion for the type of `api_field`
+            pub fn convert(
+                excel_data: Option<&DataType>,
+                api_field: &TicketField,
+                worksheet: &Worksheet,
+            ) -> Result<Self, ConversionError> {
+                match api_field.field_type.as_str() {
+                    "integer" => Self::from_integer(excel_data, api_field, worksheet),
+                    "decimal" => Self::from_decimal(excel_data, api_field, worksheet),
+                    "date" => Self::from_date(excel_data, api_field, &worksheet.timezone),
+                    "checkbox" => Self::from_checkbox(excel_data, api_field, worksheet),
+                    "text" => Self::from_text(excel_data, api_field),
+                    "tagger" => Self::from_tagger(excel_data, api_field),
+                    unknown => Err(ConversionError::UnsupportedFieldType(unknown.to_string())),
+                }
+            }
+
+            /// Sent for `null_if_empty` fields, clears the value of an existing ticket
+            pub fn null(api_field: &TicketField) -> Self {
+                Self {
+                    id: api_field.id,
+                    value: ApiValue::Null,
+                }
+            }
+
             pub fn from_integer(
                 excel_data: Option<&DataType>,
                 api_field: &TicketField,
-            ) -> Result<Self> {
-                let id = api_field.id;
-                let value = excel_data
-                    // .and_then(DataType::get_int)
-                    .and_then(DataType::get_float) // apparently even whole numbers are stored as floats? we should look into this later
-                    .map(|x| x.to_string())
-                    .map(ApiValue::Common)
-                    .ok_or(anyhow!("Could not parse {:#?} as integer", excel_data));
-                Ok(Self { id, value: value? })
+                worksheet: &Worksheet,
+            ) -> Result<Self, ConversionError> {
+                let value = integer(excel_data, worksheet)?;
+                Ok(Self {
+                    id: api_field.id,
+                    value: ApiValue::Common(value.to_string()),
+                })
             }
 
             pub fn from_decimal(
                 excel_data: Option<&DataType>,
                 api_field: &TicketField,
-            ) -> Result<Self> {
-                let id = api_field.id;
-                let value = excel_data
-                    .and_then(DataType::get_float)
-                    .map(|x| x.to_string())
-                    .map(ApiValue::Common)
-                    .ok_or(anyhow!("Could not parse cell as float"));
-                Ok(Self { id, value: value? })
+                worksheet: &Worksheet,
+            ) -> Result<Self, ConversionError> {
+                let value = number(excel_data, worksheet)?;
+                Ok(Self {
+                    id: api_field.id,
+                    value: ApiValue::Common(value.to_string()),
+                })
             }
 
+            /// Besides boolean cells, takes the words and numbers listed in
+            /// `worksheet.checkbox_true` and `worksheet.checkbox_false`
             pub fn from_checkbox(
                 excel_data: Option<&DataType>,
                 api_field: &TicketField,
-            ) -> Result<Self> {
-                let id = api_field.id;
-                let value = excel_data
-                    .and_then(DataType::get_bool)
-                    .map(ApiValue::Checkbox)
-                    .ok_or(anyhow!("Could not parse cell as boolean"));
-                Ok(Self { id, value: value? })
+                worksheet: &Worksheet,
+            ) -> Result<Self, ConversionError> {
+                let value = match excel_data {
+                    None | Some(DataType::Empty) => return Err(ConversionError::Empty),
+                    Some(DataType::Bool(value)) => *value,
+                    Some(other) => {
+                        let word = cell_to_string(other).unwrap_or_default().to_lowercase();
+                        let listed =
+                            |words: &[String]| words.iter().any(|w| w.to_lowercase() == word);
+                        if listed(&worksheet.checkbox_true) {
+                            true
+                        } else if listed(&worksheet.checkbox_false) {
+                            false
+                        } else {
+                            return Err(ConversionError::NotABoolean(other.to_string()));
+                        }
+                    }
+                };
+                Ok(Self {
+                    id: api_field.id,
+                    value: ApiValue::Checkbox(value),
+                })
             }
 
             pub fn from_text(
                 excel_data: Option<&DataType>,
                 api_field: &TicketField,
-            ) -> Result<Self> {
-                let id = api_field.id;
-                let value = excel_data
-                    .and_then(DataType::get_string)
-                    .map(str::to_string)
-                    .map(ApiValue::Common)
-                    .ok_or(anyhow!("Could not parse cell as string"));
-                Ok(Self { id, value: value? })
+            ) -> Result<Self, ConversionError> {
+                let value = text(excel_data)?;
+                Ok(Self {
+                    id: api_field.id,
+                    value: ApiValue::Common(value.to_string()),
+                })
             }
 
             pub fn from_date(
                 excel_data: Option<&DataType>,
                 api_field: &TicketField,
                 timezone: &str,
-            ) -> Result<Self> {
+            ) -> Result<Self, ConversionError> {
                 let zone = match timezone {
                     "Acre" => chrono_tz::Brazil::Acre,
                     "DeNoronha" => chrono_tz::Brazil::DeNoronha,
                     "East" => chrono_tz::Brazil::East,
                     "West" => chrono_tz::Brazil::West,
-                    _ => return Err(anyhow!("Unknown timezone")),
+                    _ => return Err(ConversionError::UnknownTimezone(timezone.to_string())),
                 };
 
-                let id = api_field.id;
-                let value = excel_data
-                    .and_then(DataType::get_float)
-                    .map(|x| (x - 25569_f64) * 86400_f64)
-                    .map(|x| NaiveDateTime::from_timestamp(x.round() as i64, 0))
-                    .map(|x| zone.from_local_datetime(&x).unwrap())
-                    .map(|x| x.with_timezone(&Utc))
-                    .map(|x| x.date().naive_utc())
-                    .map(ApiValue::Date)
-                    .ok_or(anyhow!("Could not parse {:#?} as datetime", excel_data));
-                Ok(Self { id, value: value? })
+                let days = match excel_data {
+                    None | Some(DataType::Empty) => return Err(ConversionError::Empty),
+                    Some(DataType::Float(days)) => *days,
+                    Some(other) => return Err(ConversionError::NotADate(other.to_string())),
+                };
+                let not_a_date = || ConversionError::NotADate(days.to_string());
+                let local = excel_datetime(days).ok_or_else(not_a_date)?;
+                // when the clocks went forward at midnight that day starts an hour later
+                let moment = zone
+                    .from_local_datetime(&local)
+                    .earliest()
+                    .or_else(|| {
+                        zone.from_local_datetime(&(local + Duration::hours(1)))
+                            .earliest()
+                    })
+                    .ok_or_else(not_a_date)?;
+                let date = moment.with_timezone(&Utc).date().naive_utc();
+                Ok(Self {
+                    id: api_field.id,
+                    value: ApiValue::Date(date),
+                })
             }
 
             pub fn from_tagger(
                 excel_data: Option<&DataType>,
                 api_field: &TicketField,
-            ) -> Result<Self> {
-                let id = api_field.id;
-                let value = excel_data
-                    .and_then(DataType::get_string)
-                    .and_then(|xcl| {
-                        api_field
-                            .custom_field_options
-                            .as_ref()
-                            .and_then(|v| v.into_iter().find(|field| field.name == xcl))
-                    })
-                    .map(|x| x.value.clone())
-                    .ok_or(anyhow!("Could not parse cell as dropdown menu option"));
-                Ok(Self { id, value: value? })
+            ) -> Result<Self, ConversionError> {
+                let name = text(excel_data)?;
+                let options = api_field
+                    .custom_field_options
+                    .as_deref()
+                    .unwrap_or_default();
+                let option = options
+                    .iter()
+                    .find(|option| option.name == name)
+                    .ok_or_else(|| ConversionError::UnknownOption {
+                        value: name.to_string(),
+                        options: options
+                            .iter()
+                            .map(|option| option.name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    })?;
+                Ok(Self {
+                    id: api_field.id,
+                    value: option.value.clone(),
+                })
+            }
+        }
+
+        /// The text of a cell, telling empty cells apart from ones holding a number or
+        /// a boolean
+        fn text(cell: Option<&DataType>) -> Result<&str, ConversionError> {
+            match cell {
+                None | Some(DataType::Empty) => Err(ConversionError::Empty),
+                Some(DataType::String(text)) => Ok(text),
+                Some(other) => Err(ConversionError::NotText(other.to_string())),
+            }
+        }
+
+        /// Empty cells and ones holding only whitespace
+        pub fn is_blank(cell: Option<&DataType>) -> bool {
+            match cell {
+                None | Some(DataType::Empty) => true,
+                Some(DataType::String(text)) => text.trim().is_empty(),
+                Some(_) => false,
+            }
+        }
+
+        /// Numbers are usually stored as floats, but may also be typed in as text
+        /// written with the sheet's separators, like "1.234,56"
+        fn number(cell: Option<&DataType>, worksheet: &Worksheet) -> Result<f64, ConversionError> {
+            match cell {
+                None | Some(DataType::Empty) => Err(ConversionError::Empty),
+                Some(DataType::Float(value)) => Ok(*value),
+                Some(DataType::Int(value)) => Ok(*value as f64),
+                Some(DataType::String(text)) => {
+                    parse_number(text, worksheet.decimal_separator, worksheet.thousands())
+                        .ok_or_else(|| ConversionError::NotANumber(text.clone()))
+                }
+                Some(other) => Err(ConversionError::NotANumber(other.to_string())),
+            }
+        }
+
+        /// Zendesk keeps integer fields as 32-bit numbers
+        const INTEGER_RANGE: RangeInclusive<i64> = i32::MIN as i64..=i32::MAX as i64;
+
+        /// Fractions are refused rather than rounded, "42,5" is most likely a typo
+        /// in an integer column
+        fn integer(cell: Option<&DataType>, worksheet: &Worksheet) -> Result<i64, ConversionError> {
+            let value = match cell {
+                Some(DataType::Int(value)) => *value,
+                _ => {
+                    let value = number(cell, worksheet)?;
+                    if value.fract() != 0.0 {
+                        return Err(ConversionError::NotAnInteger(
+                            cell.map(DataType::to_string).unwrap_or_default(),
+                        ));
+                    }
+                    if value < *INTEGER_RANGE.start() as f64 || value > *INTEGER_RANGE.end() as f64
+                    {
+                        return Err(out_of_range(value));
+                    }
+                    value as i64
+                }
+            };
+            if !INTEGER_RANGE.contains(&value) {
+                return Err(out_of_range(value));
+            }
+            Ok(value)
+        }
+
+        fn out_of_range(value: impl ToString) -> ConversionError {
+            ConversionError::OutOfRange {
+                value: value.to_string(),
+                min: *INTEGER_RANGE.start(),
+                max: *INTEGER_RANGE.end(),
+            }
+        }
+
+        /// Thousands separators are only taken in groups of three digits, so that
+        /// "1.5" in a sheet using "," for decimals is refused instead of read as 15
+        fn parse_number(text: &str, decimal: char, thousands: char) -> Option<f64> {
+            let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+            let (sign, digits) = match text.strip_prefix('-') {
+                Some(digits) => ("-", digits),
+                None => ("", text.strip_prefix('+').unwrap_or(&text)),
+            };
+            let mut parts = digits.splitn(2, decimal);
+            let whole = parts.next()?;
+            let fraction = parts.next();
+            let groups: Vec<&str> = whole.split(thousands).collect();
+            let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+            let grouped = groups.len() == 1
+                || groups.iter().enumerate().all(|(i, group)| {
+                    group.len() == 3 || (i == 0 && (1..=3).contains(&group.len()))
+                });
+            let valid = grouped
+                && groups.iter().all(|group| is_digits(group))
+                && fraction.map_or(!whole.is_empty(), |f| !f.is_empty() && is_digits(f));
+            if !valid {
+                return None;
+            }
+            format!("{}0{}.{}", sign, groups.concat(), fraction.unwrap_or("0"))
+                .parse()
+                .ok()
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            fn worksheet(decimal_separator: char) -> Worksheet {
+                toml::from_str(&format!(
+                    "top_row = 2\ntimezone = \"East\"\ndecimal_separator = \"{}\"",
+                    decimal_separator
+                ))
+                .unwrap()
+            }
+
+            #[test]
+            fn parse_number_takes_grouped_thousands() {
+                assert_eq!(parse_number("1.234,56", ',', '.'), Some(1234.56));
+                assert_eq!(parse_number("1,234.56", '.', ','), Some(1234.56));
+                assert_eq!(parse_number("-1.234", ',', '.'), Some(-1234.0));
+            }
+
+            #[test]
+            fn parse_number_refuses_what_is_not_a_number() {
+                assert_eq!(parse_number("1.5", ',', '.'), None);
+                assert_eq!(parse_number("12.34,5", ',', '.'), None);
+                assert_eq!(parse_number("-", ',', '.'), None);
+                assert_eq!(parse_number("", ',', '.'), None);
+            }
+
+            #[test]
+            fn parse_number_takes_a_fraction_without_units() {
+                assert_eq!(parse_number(",5", ',', '.'), Some(0.5));
+            }
+
+            #[test]
+            fn integer_refuses_decimals() {
+                let cell = DataType::String("42,5".to_string());
+                assert_eq!(
+                    integer(Some(&cell), &worksheet(',')),
+                    Err(ConversionError::NotAnInteger("42,5".to_string()))
+                );
+                let cell = DataType::String("1.000".to_string());
+                assert_eq!(integer(Some(&cell), &worksheet(',')), Ok(1000));
             }
         }
     }